@@ -1,27 +1,49 @@
-use std::{fmt::Display, path::PathBuf};
+use std::{fmt::Display, num::NonZeroUsize, path::PathBuf};
 
-use chrono::{DateTime, NaiveDate, NaiveDateTime, NaiveTime, Utc};
-use clap::{command, Args, Parser, ValueEnum};
+use chrono::{DateTime, FixedOffset, NaiveDate, NaiveDateTime, NaiveTime, TimeDelta, Utc};
+use chrono_tz::Tz;
+use clap::{Args, Parser, Subcommand, ValueEnum};
 
 use crate::{
     data_providers::{
-        flightradar24_provider::FlightRadar24ApiProvider, json_provider::FlightDataFileProvider,
-        FlightDataProvider,
+        adsbx_provider::AdsbExchangeTraceFileProvider, csv_provider::FlightRadar24CsvFileProvider,
+        flightradar24_provider::FlightRadar24ApiProvider, gpx_provider::GpxFileProvider,
+        json_provider::FlightDataFileProvider, kml_provider::KmlFileProvider,
+        opensky_provider::OpenSkyApiProvider, FlightDataProvider,
+    },
+    dry_run::DryRunFormat,
+    flight_cache::FlightCache,
+    image_geotagger::{GapAction, ImageGeotagger, ImageOutput},
+    image_walker::ImageWalker,
+    models::{
+        camera_clock::CameraClock,
+        flight_geodata::FlightGeodata,
+        interpolator::{Extrapolation, Interpolation},
+        result::{GTError, GTResult},
     },
-    models::result::{GTError, GTResult},
 };
 
 #[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, ValueEnum)]
 pub enum FlightDataSrc {
     Json,
+    Gpx,
+    Kml,
+    Csv,
+    Adsbx,
     Api,
+    Opensky,
 }
 
 impl Display for FlightDataSrc {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let text = match self {
             Self::Json => "json",
+            Self::Gpx => "gpx",
+            Self::Kml => "kml",
+            Self::Csv => "csv",
+            Self::Adsbx => "adsbx",
             Self::Api => "api",
+            Self::Opensky => "opensky",
         };
         f.write_str(text)
     }
@@ -42,6 +64,60 @@ fn parse_dod_date(s: &str) -> Result<DateTime<Utc>, String> {
     }
 }
 
+fn parse_flight_time(s: &str) -> Result<DateTime<Utc>, String> {
+    DateTime::parse_from_rfc3339(s)
+        .map(|dt| dt.with_timezone(&Utc))
+        .map_err(|e| format!("Invalid flight time provided ('{s}'). Error: {e}"))
+}
+
+fn try_parse_time_offset(s: &str) -> GTResult<TimeDelta> {
+    let invalid = || {
+        GTError::Args(format!(
+            "Invalid time offset provided ('{s}'). Expected ±HH:MM[:SS]."
+        ))
+    };
+
+    let (sign, rest) = match s.strip_prefix('-') {
+        Some(rest) => (-1, rest),
+        None => (1, s.strip_prefix('+').unwrap_or(s)),
+    };
+
+    let parts = rest
+        .split(':')
+        .map(|part| part.parse::<u32>().map_err(|_| invalid()))
+        .collect::<GTResult<Vec<_>>>()?;
+
+    let (hours, minutes, seconds) = match parts.as_slice() {
+        [hours, minutes] if *minutes < 60 => (*hours, *minutes, 0),
+        [hours, minutes, seconds] if *minutes < 60 && *seconds < 60 => (*hours, *minutes, *seconds),
+        _ => return Err(invalid()),
+    };
+    let seconds = hours
+        .checked_mul(3600)
+        .and_then(|h| h.checked_add(minutes * 60 + seconds))
+        .ok_or(GTError::Args(format!("Time offset out of range ('{s}').")))?;
+
+    Ok(TimeDelta::seconds(sign * i64::from(seconds)))
+}
+
+fn parse_time_offset(s: &str) -> Result<TimeDelta, String> {
+    try_parse_time_offset(s).map_err(|e| e.to_string())
+}
+
+fn parse_utc_offset(s: &str) -> Result<FixedOffset, String> {
+    let offset = parse_time_offset(s)?;
+
+    i32::try_from(offset.num_seconds())
+        .ok()
+        .and_then(FixedOffset::east_opt)
+        .ok_or(format!("UTC offset out of range ('{s}')."))
+}
+
+fn parse_camera_tz(s: &str) -> Result<Tz, String> {
+    s.parse::<Tz>()
+        .map_err(|e| format!("Invalid time zone provided ('{s}'). Error: {e}"))
+}
+
 #[derive(Args)]
 #[command(version, about)]
 pub struct TagArgs {
@@ -61,31 +137,569 @@ pub struct TagArgs {
     #[arg(short, long)]
     pub json_file: Option<PathBuf>,
 
+    /// File path to a flight track in any supported format. The format is detected from the file
+    /// contents, and takes the place of `--src`.
+    #[arg(short, long, conflicts_with_all = ["src", "json_file"])]
+    pub track_file: Option<PathBuf>,
+
+    /// File path to a GPX track log recorded during the flight.
+    #[arg(long)]
+    pub gpx_file: Option<PathBuf>,
+
+    /// File path to a KML or KMZ flight track.
+    #[arg(long)]
+    pub kml_file: Option<PathBuf>,
+
+    /// File path to a FlightRadar24 CSV export of the flight.
+    #[arg(long)]
+    pub csv_file: Option<PathBuf>,
+
+    /// File path to an ADS-B Exchange / readsb trace (`trace_full_<icao>.json`) of the flight.
+    #[arg(long)]
+    pub trace_file: Option<PathBuf>,
+
+    /// ICAO 24-bit address (hex) of the aircraft, used by the opensky source.
+    #[arg(long)]
+    pub icao24: Option<String>,
+
+    /// Any time during the flight (RFC 3339), used by the opensky source to find the flight.
+    /// Defaults to the aircraft's live track.
+    #[arg(long, value_parser = parse_flight_time)]
+    pub flight_time: Option<DateTime<Utc>>,
+
+    /// FlightRadar24 flight id (hex) of the flight, used by the api source instead of looking the
+    /// flight up by its code.
+    #[arg(long, conflicts_with = "pick")]
+    pub flight_id: Option<String>,
+
+    /// List the flights found by the api source and choose one interactively.
+    #[arg(long)]
+    pub pick: bool,
+
+    /// Download flight data again even if it is already cached.
+    #[arg(long, conflicts_with = "offline")]
+    pub refresh: bool,
+
+    /// Only use cached flight data, without accessing the network.
+    #[arg(long)]
+    pub offline: bool,
+
+    /// Also write the flight data downloaded by the api source to this path, for later use with
+    /// `--json-file`.
+    #[arg(long)]
+    pub save_track: Option<PathBuf>,
+
+    /// Base URL of the OpenSky Network REST API.
+    #[arg(long, default_value = OpenSkyApiProvider::DEFAULT_BASE_URL)]
+    pub opensky_url: String,
+
+    /// Also write the loaded flight track out as GeoJSON to this path.
+    #[arg(long)]
+    pub export_geojson: Option<PathBuf>,
+
+    /// Only geotag files matching this glob pattern. May be given more than once. Defaults to
+    /// `*.jpg` and `*.jpeg`, so that videos and raw files in a camera folder are left alone.
+    #[arg(long)]
+    pub include: Vec<String>,
+
+    /// Skip files and directories matching this glob pattern. May be given more than once.
+    #[arg(long)]
+    pub exclude: Vec<String>,
+
+    /// How many levels of subdirectories of the images directory to search. Unlimited by default.
+    #[arg(long)]
+    pub max_depth: Option<usize>,
+
+    /// Directory to write geotagged images to. Defaults to `geotagged` inside the images
+    /// directory.
+    #[arg(short, long)]
+    pub output_dir: Option<PathBuf>,
+
+    /// Overwrite the original images instead of writing geotagged copies.
+    #[arg(long, conflicts_with = "output_dir")]
+    pub in_place: bool,
+
+    /// When tagging in place, keep each original image next to it with this suffix appended.
+    #[arg(long, requires = "in_place")]
+    pub backup_suffix: Option<String>,
+
+    /// Shift every image timestamp by this much (±HH:MM:SS) to correct the camera clock.
+    #[arg(long, value_parser = parse_time_offset, allow_hyphen_values = true, conflicts_with = "sync_photo")]
+    pub time_offset: Option<TimeDelta>,
+
+    /// Photo of a known moment, such as a seat-back map showing the time, from which the camera
+    /// clock offset is worked out. Without `--sync-time`, it is taken to be a photo of the takeoff
+    /// roll, and synced with the takeoff in the flight track.
+    #[arg(long)]
+    pub sync_photo: Option<PathBuf>,
+
+    /// The actual time (RFC 3339) at which `--sync-photo` was taken.
+    #[arg(long, value_parser = parse_flight_time, requires = "sync_photo")]
+    pub sync_time: Option<DateTime<Utc>>,
+
+    /// IANA time zone the camera clock was set to (e.g. Europe/Malta), for images which don't
+    /// record their UTC offset. Takes precedence over the GPS time stamp in the image, as the
+    /// camera's last GPS fix can be hours old when it is in airplane mode.
+    #[arg(long, value_parser = parse_camera_tz)]
+    pub camera_tz: Option<Tz>,
+
+    /// UTC offset (±HH:MM) the camera clock was set to, for images which don't record their UTC
+    /// offset. `--camera-tz` takes precedence, and like it, this is used ahead of the GPS time
+    /// stamp in the image.
+    #[arg(long, value_parser = parse_utc_offset, allow_hyphen_values = true)]
+    pub camera_utc_offset: Option<FixedOffset>,
+
+    /// How to estimate positions between the points of the flight track.
+    #[arg(long, default_value_t = Interpolation::GreatCircle)]
+    pub interpolation: Interpolation,
+
+    /// Longest gap in seconds between track points to interpolate across. Images in longer gaps
+    /// are handled according to `--on-gap`.
+    #[arg(long)]
+    pub max_gap: Option<u32>,
+
+    /// What to do with images in a gap longer than `--max-gap`.
+    #[arg(long, requires = "max_gap", default_value_t = GapAction::Skip)]
+    pub on_gap: GapAction,
+
+    /// How to place images taken before the first or after the last track point. Without it,
+    /// such images are skipped.
+    #[arg(long)]
+    pub extrapolate: Option<Extrapolation>,
+
+    /// Furthest in seconds to dead reckon past either end of the track.
+    #[arg(long, default_value_t = 600)]
+    pub dead_reckoning_limit: u32,
+
+    /// Report the position computed for each image instead of writing anything, including the
+    /// images, the flight data cache, `--save-track` and `--export-geojson`. Status messages go to
+    /// stderr, leaving the report alone on stdout.
+    #[arg(long)]
+    pub dry_run: bool,
+
+    /// How to print the dry run report.
+    #[arg(long, requires = "dry_run", default_value_t = DryRunFormat::Table)]
+    pub dry_run_format: DryRunFormat,
+
+    /// Also write the outcome of every image to this path, as JSON or CSV according to its
+    /// extension.
+    #[arg(long, conflicts_with = "dry_run")]
+    pub report: Option<PathBuf>,
+
+    /// Number of images to process at once. Defaults to the number of CPUs.
+    #[arg(long)]
+    pub jobs: Option<NonZeroUsize>,
+
     /// Path to directory containing all images to geotag.
     pub images_dir: PathBuf,
 }
 
 impl TagArgs {
-    pub fn try_get_provider(&self) -> GTResult<Box<dyn FlightDataProvider>> {
+    /// Only JPEG images can be geotagged. Patterns match case-insensitively.
+    const DEFAULT_INCLUDES: [&'static str; 2] = ["*.jpg", "*.jpeg"];
+
+    pub fn jobs(&self) -> NonZeroUsize {
+        self.jobs
+            .unwrap_or_else(|| std::thread::available_parallelism().unwrap_or(NonZeroUsize::MIN))
+    }
+
+    pub fn camera_clock(&self) -> CameraClock {
+        CameraClock {
+            tz: self.camera_tz,
+            utc_offset: self.camera_utc_offset,
+        }
+    }
+
+    /// Works out how to correct the camera clock, reading `--sync-photo` if one was given.
+    pub fn clock_correction(&self) -> GTResult<ClockCorrection> {
+        let Some(ref photo) = self.sync_photo else {
+            return Ok(ClockCorrection::Offset(
+                self.time_offset.unwrap_or_default(),
+            ));
+        };
+
+        let camera_time = ImageGeotagger::read_image_timestamp(photo, &self.camera_clock())?.time;
+
+        Ok(match self.sync_time {
+            Some(sync_time) => ClockCorrection::Offset(sync_offset(camera_time, sync_time)),
+            None => ClockCorrection::Takeoff(camera_time),
+        })
+    }
+
+    pub fn image_output(&self) -> ImageOutput {
+        if self.in_place {
+            ImageOutput::InPlace {
+                backup_suffix: self.backup_suffix.clone(),
+            }
+        } else {
+            ImageOutput::Directory(
+                self.output_dir
+                    .clone()
+                    .unwrap_or_else(|| self.images_dir.join("geotagged")),
+            )
+        }
+    }
+
+    /// Finds the images to geotag, as paths relative to the images directory.
+    pub fn find_images(&self) -> GTResult<Vec<PathBuf>> {
+        let mut excludes = self.exclude.clone();
+        let walker = match self.image_output() {
+            ImageOutput::Directory(output_dir) => {
+                ImageWalker::new(self.images_dir.clone()).with_skipped_dir(output_dir)
+            }
+            ImageOutput::InPlace { backup_suffix } => {
+                // Backups from earlier runs shouldn't be tagged themselves.
+                excludes.extend(backup_suffix.map(|suffix| format!("*{suffix}")));
+                ImageWalker::new(self.images_dir.clone())
+            }
+        };
+
+        let includes = if self.include.is_empty() {
+            Self::DEFAULT_INCLUDES.map(str::to_string).to_vec()
+        } else {
+            self.include.clone()
+        };
+
+        walker
+            .with_includes(includes)
+            .with_excludes(excludes)
+            .with_max_depth(self.max_depth)
+            .walk()
+    }
+
+    /// Picks the flight data provider. `time_offset` corrects the photo timestamps which the api
+    /// source uses to find the flight.
+    pub fn try_get_provider(
+        &self,
+        time_offset: TimeDelta,
+    ) -> GTResult<Box<dyn FlightDataProvider>> {
+        if let Some(ref path) = self.track_file {
+            return Ok(Box::new(FlightDataFileProvider::new(
+                path.clone(),
+                self.flight_code.clone(),
+            )));
+        }
+
         match self.flight_data_src {
             FlightDataSrc::Json => {
                 if let Some(ref path) = self.json_file {
-                    Ok(Box::new(FlightDataFileProvider::new(path.clone())))
+                    Ok(Box::new(FlightDataFileProvider::new(
+                        path.clone(),
+                        self.flight_code.clone(),
+                    )))
                 } else {
-                    GTResult::Err(GTError::Args("Invalid configuration.".to_string()))
+                    GTResult::Err(GTError::Args(
+                        "--json-file or --track-file is required when using the json source."
+                            .to_string(),
+                    ))
                 }
             }
-            FlightDataSrc::Api => Ok(Box::new(FlightRadar24ApiProvider::new(
-                self.flight_code.clone(),
-                self.date_of_departure,
-            ))),
+            FlightDataSrc::Gpx => {
+                if let Some(ref path) = self.gpx_file {
+                    Ok(Box::new(GpxFileProvider::new(
+                        path.clone(),
+                        self.flight_code.clone(),
+                    )))
+                } else {
+                    GTResult::Err(GTError::Args(
+                        "--gpx-file is required when using the gpx source.".to_string(),
+                    ))
+                }
+            }
+            FlightDataSrc::Kml => {
+                if let Some(ref path) = self.kml_file {
+                    Ok(Box::new(KmlFileProvider::new(
+                        path.clone(),
+                        self.flight_code.clone(),
+                    )))
+                } else {
+                    GTResult::Err(GTError::Args(
+                        "--kml-file is required when using the kml source.".to_string(),
+                    ))
+                }
+            }
+            FlightDataSrc::Csv => {
+                if let Some(ref path) = self.csv_file {
+                    Ok(Box::new(FlightRadar24CsvFileProvider::new(path.clone())))
+                } else {
+                    GTResult::Err(GTError::Args(
+                        "--csv-file is required when using the csv source.".to_string(),
+                    ))
+                }
+            }
+            FlightDataSrc::Adsbx => {
+                if let Some(ref path) = self.trace_file {
+                    Ok(Box::new(AdsbExchangeTraceFileProvider::new(path.clone())))
+                } else {
+                    GTResult::Err(GTError::Args(
+                        "--trace-file is required when using the adsbx source.".to_string(),
+                    ))
+                }
+            }
+            FlightDataSrc::Api => {
+                let image_paths = self
+                    .find_images()?
+                    .into_iter()
+                    .map(|path| self.images_dir.join(path))
+                    .collect::<Vec<_>>();
+                let photo_timestamps =
+                    ImageGeotagger::read_image_timestamps(&image_paths, &self.camera_clock())
+                        .into_iter()
+                        .map(|t| t + time_offset)
+                        .collect();
+
+                Ok(Box::new(
+                    FlightRadar24ApiProvider::new(self.flight_code.clone(), self.date_of_departure)
+                        .with_photo_timestamps(photo_timestamps)
+                        .with_flight_id(self.flight_id.clone())
+                        .with_interactive_pick(self.pick)
+                        .with_cache(FlightCache::open_default()?)
+                        .with_refresh(self.refresh)
+                        .with_offline(self.offline)
+                        .with_save_path(self.save_track.clone())
+                        .with_read_only(self.dry_run),
+                ))
+            }
+            FlightDataSrc::Opensky => {
+                if let Some(ref icao24) = self.icao24 {
+                    Ok(Box::new(
+                        OpenSkyApiProvider::new(icao24.clone(), self.flight_time)
+                            .with_base_url(self.opensky_url.clone()),
+                    ))
+                } else {
+                    GTResult::Err(GTError::Args(
+                        "--icao24 is required when using the opensky source.".to_string(),
+                    ))
+                }
+            }
+        }
+    }
+}
+
+/// How to correct the camera clock, worked out once at the start of a run.
+#[derive(Clone, Copy)]
+pub enum ClockCorrection {
+    /// A known offset, either as given or synced from a photo of a known time.
+    Offset(TimeDelta),
+    /// The camera time of a photo of the takeoff roll, to be synced with the flight track once it
+    /// is loaded.
+    Takeoff(DateTime<Utc>),
+}
+
+impl ClockCorrection {
+    /// The offset as far as it is known before the flight track is loaded.
+    pub fn known_offset(&self) -> TimeDelta {
+        match self {
+            Self::Offset(offset) => *offset,
+            Self::Takeoff(_) => TimeDelta::zero(),
+        }
+    }
+
+    /// The offset to apply to every image timestamp, syncing a takeoff photo with `flight_data`.
+    pub fn resolve(self, flight_data: &FlightGeodata) -> GTResult<TimeDelta> {
+        match self {
+            Self::Offset(offset) => Ok(offset),
+            Self::Takeoff(camera_time) => {
+                let takeoff = flight_data.takeoff_time().ok_or(GTError::MissingData(
+                    "Can't sync the camera clock, as the flight track has no takeoff. Use \
+                     --sync-time instead."
+                        .to_string(),
+                ))?;
+
+                Ok(sync_offset(camera_time, takeoff))
+            }
         }
     }
 }
 
+fn sync_offset(camera_time: DateTime<Utc>, actual_time: DateTime<Utc>) -> TimeDelta {
+    let offset = actual_time - camera_time;
+
+    eprintln!(
+        "Camera clock read {camera_time} when it was {actual_time}. Using time offset of {}s.",
+        offset.num_seconds()
+    );
+
+    offset
+}
+
 #[derive(Parser)]
 #[command(name = "airmode-tagger")]
 #[command(bin_name = "airmode-tagger")]
 pub enum Cli {
-    Tag(TagArgs),
+    /// Geotag images from a flight track.
+    ///
+    /// Exits with 0 if every image was geotagged, 1 on a fatal error, 2 on a command line error,
+    /// 3 if some images were not geotagged, and 4 if none were.
+    Tag(Box<TagArgs>),
+    /// Manage the cache of downloaded flight data.
+    #[command(subcommand)]
+    Cache(CacheCommand),
+}
+
+#[derive(Subcommand)]
+pub enum CacheCommand {
+    /// List the cached flight data.
+    List,
+    /// Delete all cached flight data.
+    Clear,
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::{Path, PathBuf};
+
+    use chrono::{DateTime, TimeDelta, Utc};
+    use clap::Parser;
+    use exif::{Field, In, Tag, Value};
+    use img_parts::{jpeg::Jpeg, ImageEXIF};
+
+    use super::{try_parse_time_offset, Cli, ClockCorrection};
+    use crate::{
+        image_geotagger::ImageGeotagger,
+        models::flight_geodata::{FlightGeodata, GeoPosition},
+        run_summary::RunSummary,
+    };
+
+    fn position(seconds: i64, altitude: i64) -> GeoPosition {
+        GeoPosition {
+            timestamp: DateTime::<Utc>::UNIX_EPOCH + TimeDelta::seconds(seconds),
+            latitude: 51.47,
+            longitude: -0.45,
+            altitude,
+            speed: None,
+            heading: None,
+            vertical_speed: None,
+        }
+    }
+
+    /// Writes a minimal JPEG taken at 1970-01-01 00:00:30 UTC.
+    fn write_jpeg(path: &Path) {
+        let mut writer = exif::experimental::Writer::new();
+        let fields = [
+            (Tag::DateTimeOriginal, "1970:01:01 00:00:30"),
+            (Tag::OffsetTimeOriginal, "+00:00"),
+        ]
+        .map(|(tag, value)| Field {
+            tag,
+            ifd_num: In::PRIMARY,
+            value: Value::Ascii(vec![value.as_bytes().to_vec()]),
+        });
+        for field in &fields {
+            writer.push_field(field);
+        }
+        let mut exif = std::io::Cursor::new(Vec::new());
+        writer
+            .write(&mut exif, false)
+            .unwrap_or_else(|e| panic!("{e}"));
+
+        // img-parts inserts the EXIF segment after the first three, so comments pad it out, and
+        // it needs a scan to read the image back.
+        let mut src = b"\xFF\xD8".to_vec();
+        for _ in 0..3 {
+            src.extend(b"\xFF\xFE\x00\x03-");
+        }
+        src.extend(b"\xFF\xDA\x00\x08\x01\x01\x00\x00\x3F\x00\x00\x00\xFF\xD9");
+        let mut jpeg = Jpeg::from_bytes(src.into()).unwrap_or_else(|e| panic!("{e}"));
+        jpeg.set_exif(Some(exif.into_inner().into()));
+        let mut file = std::fs::File::create(path).unwrap();
+        jpeg.encoder().write_to(&mut file).unwrap();
+    }
+
+    fn offset(s: &str) -> Option<i64> {
+        try_parse_time_offset(s).ok().map(|o| o.num_seconds())
+    }
+
+    #[test]
+    fn mixed_camera_folder_only_tags_jpegs() {
+        let dir = std::env::temp_dir().join(format!("cli-mixed-folder-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        write_jpeg(&dir.join("IMG_0001.JPG"));
+        for name in ["IMG_0002.MOV", "IMG_0003.CR2", ".DS_Store"] {
+            std::fs::write(dir.join(name), b"not a jpeg").unwrap();
+        }
+
+        let Cli::Tag(args) = Cli::try_parse_from([
+            "airmode-tagger".as_ref(),
+            "tag".as_ref(),
+            "--flight-code=TEST1".as_ref(),
+            "--date-of-departure=01 Jan 1970".as_ref(),
+            dir.as_os_str(),
+        ])
+        .unwrap_or_else(|e| panic!("{e}")) else {
+            panic!("Expected the tag command.");
+        };
+
+        let images = args.find_images().unwrap_or_else(|e| panic!("{e}"));
+        assert_eq!(images, [PathBuf::from("IMG_0001.JPG")]);
+
+        let flight_data = FlightGeodata::new(
+            "TEST1".to_string(),
+            vec![position(0, 100), position(60, 200)],
+        )
+        .unwrap_or_else(|e| panic!("{e}"));
+        let mapper = ImageGeotagger::new(args.image_output(), flight_data);
+        let mut summary = RunSummary::default();
+        for relative_path in images {
+            let result =
+                mapper.apply_gps_data(&dir.join(&relative_path), &relative_path, &mut Vec::new());
+            summary.add(relative_path, &result);
+        }
+
+        assert_eq!(summary.exit_code(), 0);
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn parses_time_offsets() {
+        assert_eq!(offset("01:30"), Some(5400));
+        assert_eq!(offset("+01:30:15"), Some(5415));
+        assert_eq!(offset("-00:00:45"), Some(-45));
+        assert_eq!(offset("-11:00"), Some(-39_600));
+    }
+
+    #[test]
+    fn rejects_invalid_time_offsets() {
+        assert_eq!(offset("01:60"), None);
+        assert_eq!(offset("01:00:60"), None);
+        assert_eq!(offset("1h"), None);
+        assert_eq!(offset("01"), None);
+        assert_eq!(offset("+-01:00"), None);
+    }
+
+    #[test]
+    fn rejects_time_offsets_which_overflow() {
+        assert_eq!(offset("1193046:28:15"), Some(4_294_967_295));
+        assert_eq!(offset("1193046:28:16"), None);
+        assert_eq!(offset("4294967295:00"), None);
+    }
+
+    #[test]
+    fn takeoff_photo_is_synced_with_the_track() {
+        let start = DateTime::<Utc>::UNIX_EPOCH;
+        let positions = vec![position(0, 25), position(60, 25), position(120, 400)];
+        let flight_data =
+            FlightGeodata::new("TEST1".to_string(), positions).unwrap_or_else(|e| panic!("{e}"));
+
+        // The camera clock is an hour fast.
+        let correction = ClockCorrection::Takeoff(start + TimeDelta::seconds(3660));
+        assert_eq!(correction.known_offset(), TimeDelta::zero());
+        assert_eq!(
+            correction
+                .resolve(&flight_data)
+                .unwrap_or_else(|e| panic!("{e}")),
+            TimeDelta::seconds(-3600)
+        );
+
+        let correction = ClockCorrection::Offset(TimeDelta::seconds(90));
+        assert_eq!(correction.known_offset(), TimeDelta::seconds(90));
+        assert_eq!(
+            correction
+                .resolve(&flight_data)
+                .unwrap_or_else(|e| panic!("{e}")),
+            TimeDelta::seconds(90)
+        );
+    }
 }
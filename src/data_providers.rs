@@ -1,7 +1,12 @@
 use crate::models::{flight_geodata::FlightGeodata, result::GTResult};
 
+pub mod adsbx_provider;
+pub mod csv_provider;
 pub mod flightradar24_provider;
+pub mod gpx_provider;
 pub mod json_provider;
+pub mod kml_provider;
+pub mod opensky_provider;
 
 pub trait FlightDataProvider {
     fn load_data(&self) -> GTResult<FlightGeodata>;
@@ -0,0 +1,31 @@
+use std::{fs::File, io::BufReader, path::PathBuf};
+
+use serde_json::Value;
+
+use crate::{
+    models::{flight_geodata::FlightGeodata, result::GTResult},
+    parsers::{adsbx_parser::AdsbExchangeTraceParser, json_parser::JsonParser},
+};
+
+use super::FlightDataProvider;
+
+pub struct AdsbExchangeTraceFileProvider {
+    src_path: PathBuf,
+}
+
+impl FlightDataProvider for AdsbExchangeTraceFileProvider {
+    fn load_data(&self) -> GTResult<FlightGeodata> {
+        let reader = BufReader::new(File::open(&self.src_path)?);
+        let json: Value = serde_json::from_reader(reader)?;
+        let parser = AdsbExchangeTraceParser {};
+        let geodata = parser.try_parse_geodata(json)?;
+
+        Ok(geodata)
+    }
+}
+
+impl AdsbExchangeTraceFileProvider {
+    pub fn new(src_path: PathBuf) -> Self {
+        Self { src_path }
+    }
+}
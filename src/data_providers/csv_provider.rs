@@ -0,0 +1,28 @@
+use std::{fs::File, io::BufReader, path::PathBuf};
+
+use crate::{
+    models::{flight_geodata::FlightGeodata, result::GTResult},
+    parsers::csv_parser::FlightRadar24CsvParser,
+};
+
+use super::FlightDataProvider;
+
+pub struct FlightRadar24CsvFileProvider {
+    src_path: PathBuf,
+}
+
+impl FlightDataProvider for FlightRadar24CsvFileProvider {
+    fn load_data(&self) -> GTResult<FlightGeodata> {
+        let reader = BufReader::new(File::open(&self.src_path)?);
+        let parser = FlightRadar24CsvParser {};
+        let geodata = parser.try_parse_geodata(reader)?;
+
+        Ok(geodata)
+    }
+}
+
+impl FlightRadar24CsvFileProvider {
+    pub fn new(src_path: PathBuf) -> Self {
+        Self { src_path }
+    }
+}
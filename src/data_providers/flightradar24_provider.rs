@@ -1,13 +1,20 @@
+use std::{io::Write, path::PathBuf};
+
 use chrono::{DateTime, NaiveDateTime, NaiveTime, Utc};
 use reqwest::blocking::Client;
-use scraper::{Html, Selector};
 
 use crate::{
+    flight_cache::FlightCache,
     models::{
+        flight_candidate::FlightCandidate,
         flight_geodata::FlightGeodata,
         result::{GTError, GTResult},
     },
-    parsers::json_parser::{FlightRadar24JsonParser, JsonParser},
+    parsers::{
+        flight_history_parser::FlightRadar24FlightHistoryParser,
+        flight_list_parser::FlightRadar24FlightListParser,
+        json_parser::{FlightRadar24JsonParser, JsonParser},
+    },
 };
 
 use super::FlightDataProvider;
@@ -15,14 +22,20 @@ use super::FlightDataProvider;
 pub struct FlightRadar24ApiProvider {
     flight_code: String,
     dod: DateTime<Utc>,
+    photo_timestamps: Vec<DateTime<Utc>>,
+    flight_id: Option<String>,
+    interactive_pick: bool,
+    cache: Option<FlightCache>,
+    refresh: bool,
+    offline: bool,
+    save_path: Option<PathBuf>,
+    read_only: bool,
 }
 
 impl FlightRadar24ApiProvider {
     const WEBSITE_URL: &'static str = "https://www.flightradar24.com";
     const API_URL: &'static str = "https://api.flightradar24.com/common/v1";
 
-    const FLIGHTS_TABLE_SELECTOR: &'static str = "#tbl-datatable tbody";
-
     pub fn new(flight_code: String, dod: DateTime<Utc>) -> Self {
         let dod = NaiveDateTime::new(
             dod.date_naive(),
@@ -31,10 +44,88 @@ impl FlightRadar24ApiProvider {
         )
         .and_utc();
 
-        Self { flight_code, dod }
+        Self {
+            flight_code,
+            dod,
+            photo_timestamps: Vec::new(),
+            flight_id: None,
+            interactive_pick: false,
+            cache: None,
+            refresh: false,
+            offline: false,
+            save_path: None,
+            read_only: false,
+        }
+    }
+
+    /// Sets the capture times of the photos being tagged, which are used to pick the right flight
+    /// when the flight code operates more than once around the date of departure.
+    pub fn with_photo_timestamps(mut self, photo_timestamps: Vec<DateTime<Utc>>) -> Self {
+        self.photo_timestamps = photo_timestamps;
+        self
+    }
+
+    /// Uses the given FlightRadar24 flight hex directly, skipping the flight lookup.
+    pub fn with_flight_id(mut self, flight_id: Option<String>) -> Self {
+        self.flight_id = flight_id.map(|id| id.to_ascii_lowercase());
+        self
+    }
+
+    /// Asks the user to choose from the candidate flights instead of picking one automatically.
+    pub fn with_interactive_pick(mut self, interactive_pick: bool) -> Self {
+        self.interactive_pick = interactive_pick;
+        self
     }
 
-    fn get_data_link(&self, client: &Client) -> GTResult<String> {
+    /// Reuses playback data from `cache` where possible, and stores newly downloaded data in it.
+    pub fn with_cache(mut self, cache: FlightCache) -> Self {
+        self.cache = Some(cache);
+        self
+    }
+
+    /// Ignores any cached playback data and downloads it again.
+    pub fn with_refresh(mut self, refresh: bool) -> Self {
+        self.refresh = refresh;
+        self
+    }
+
+    /// Only uses cached playback data, without touching the network.
+    pub fn with_offline(mut self, offline: bool) -> Self {
+        self.offline = offline;
+        self
+    }
+
+    /// Also writes the playback JSON to `save_path`, so it can be loaded again with the json
+    /// source.
+    pub fn with_save_path(mut self, save_path: Option<PathBuf>) -> Self {
+        self.save_path = save_path;
+        self
+    }
+
+    /// Reads cached playback data without storing any, and skips `save_path`, as for a dry run.
+    pub fn with_read_only(mut self, read_only: bool) -> Self {
+        self.read_only = read_only;
+        self
+    }
+
+    fn get_listed_candidates(&self, client: &Client) -> GTResult<Vec<FlightCandidate>> {
+        let response = client
+            .get(format!("{}/flight/list.json", Self::API_URL))
+            .query(&[
+                ("query", self.flight_code.as_str()),
+                ("fetchBy", "flight"),
+                ("page", "1"),
+                ("limit", "100"),
+            ])
+            .send()?
+            .error_for_status()?;
+        let parsed_json: serde_json::Value = serde_json::from_str(response.text()?.as_str())?;
+
+        let parser = FlightRadar24FlightListParser {};
+        parser.try_parse_candidates(&parsed_json)
+    }
+
+    fn get_history_candidates(&self, client: &Client) -> GTResult<Vec<FlightCandidate>> {
         let flights_response = client
             .get(format!(
                 "{}/data/flights/{}",
@@ -44,74 +135,192 @@ impl FlightRadar24ApiProvider {
             .send()?
             .error_for_status()?;
         let flights_text = flights_response.text()?;
-        let dom = Html::parse_document(&flights_text);
 
-        let table_selector = Selector::parse(Self::FLIGHTS_TABLE_SELECTOR)?;
-        let Some(table) = dom.select(&table_selector).next() else {
-            return Err(GTError::HtmlSelection(format!(
-                "Could not find flight history table ({})",
-                Self::FLIGHTS_TABLE_SELECTOR
-            )));
-        };
+        let parser = FlightRadar24FlightHistoryParser {};
+        parser.try_parse_candidates(&self.flight_code, &flights_text, self.dod.date_naive())
+    }
 
-        let format = self.dod.format("%d %b %Y").to_string();
-        println!("Checking for row with date of departure '{format}'");
-        let Some(row) = table
-            .child_elements()
-            .filter(|tr| tr.text().any(|t| t.contains(&format)))
-            .next()
-        else {
-            return Err(GTError::HtmlSelection(format!(
-                "Could not find flight record with DoD '{format}'."
-            )));
-        };
+    fn get_flight_candidates(&self, client: &Client) -> GTResult<Vec<FlightCandidate>> {
+        match self.get_listed_candidates(client) {
+            Ok(candidates) if !candidates.is_empty() => return Ok(candidates),
+            Ok(_) => eprintln!("No flights listed. Falling back to flight history page."),
+            Err(e) => {
+                eprintln!("Flight list lookup failed ({e}). Falling back to flight history page.")
+            }
+        }
+
+        self.get_history_candidates(client)
+    }
+
+    fn pick_candidate<'a>(
+        &self,
+        candidates: &'a [FlightCandidate],
+    ) -> GTResult<&'a FlightCandidate> {
+        if !self.photo_timestamps.is_empty() {
+            return FlightCandidate::pick_for_timestamps(candidates, &self.photo_timestamps).ok_or(
+                GTError::MissingData(
+                    "No listed flight covers the capture times of the images.".to_string(),
+                ),
+            );
+        }
+
+        let dod = self.dod.date_naive();
+        let mut same_day = candidates
+            .iter()
+            .filter(|c| c.departure().is_some_and(|d| d.date_naive() == dod));
+
+        match (same_day.next(), same_day.next()) {
+            (Some(candidate), None) => Ok(candidate),
+            (None, _) => Err(GTError::MissingData(format!(
+                "No listed flight departs on {dod}."
+            ))),
+            (Some(_), Some(_)) => Err(GTError::InvalidData(format!(
+                "More than one listed flight departs on {dod}. Use --pick or --flight-id to choose one."
+            ))),
+        }
+    }
+
+    fn prompt_candidate<'a>(
+        &self,
+        candidates: &'a [FlightCandidate],
+    ) -> GTResult<&'a FlightCandidate> {
+        let suggested = self.pick_candidate(candidates).ok();
+
+        eprintln!("Choose a flight:");
+        for (i, candidate) in candidates.iter().enumerate() {
+            let marker = if suggested.is_some_and(|s| std::ptr::eq(s, candidate)) {
+                "*"
+            } else {
+                " "
+            };
+            eprintln!("{marker} {:>2}) {candidate}", i + 1);
+        }
+
+        loop {
+            match suggested {
+                Some(_) => eprint!("Flight number (blank for *): "),
+                None => eprint!("Flight number: "),
+            }
+            std::io::stderr().flush()?;
+
+            let mut line = String::new();
+            if std::io::stdin().read_line(&mut line)? == 0 {
+                return Err(GTError::Args("No flight chosen.".to_string()));
+            }
+
+            let line = line.trim();
+            if line.is_empty() {
+                if let Some(candidate) = suggested {
+                    return Ok(candidate);
+                }
+                continue;
+            }
+
+            match line.parse::<usize>() {
+                Ok(i) if (1..=candidates.len()).contains(&i) => return Ok(&candidates[i - 1]),
+                _ => eprintln!("Enter a number from 1 to {}.", candidates.len()),
+            }
+        }
+    }
 
-        let playback_btn_selector = Selector::parse(".btn-playback")?;
-        let Some(playback_btn) = row.select(&playback_btn_selector).next() else {
-            return Err(GTError::HtmlSelection(format!(
-                "Could not find playback button for flight row.",
+    fn get_playback_url(flight_id: &str, timestamp: i64) -> String {
+        format!(
+            "{}/flight-playback.json?flightId={}&timestamp={}",
+            Self::API_URL,
+            flight_id,
+            timestamp
+        )
+    }
+
+    /// Works out which flight to download, returning its flight id and departure timestamp.
+    fn resolve_flight(&self, client: &Client) -> GTResult<(String, i64)> {
+        if let Some(ref flight_id) = self.flight_id {
+            eprintln!("Using flight id '{flight_id}'.");
+
+            // The playback endpoint only uses the timestamp as a hint, so the date of departure
+            // is close enough when the actual departure time isn't known.
+            return Ok((flight_id.clone(), self.dod.timestamp()));
+        }
+
+        let candidates = self.get_flight_candidates(client)?;
+
+        if candidates.is_empty() {
+            return Err(GTError::MissingData(format!(
+                "No flights found for flight code '{}'.",
+                self.flight_code
             )));
-        };
+        }
+
+        eprintln!("Found {} candidate flights:", candidates.len());
+        for candidate in &candidates {
+            eprintln!("  {candidate}");
+        }
 
-        let Some(hex) = playback_btn.attr("data-flight-hex") else {
-            return Err(GTError::HtmlSelection(
-                "No data-flight-hex found in expected location.".to_string(),
-            ));
+        let candidate = if self.interactive_pick {
+            self.prompt_candidate(&candidates)?
+        } else {
+            self.pick_candidate(&candidates)?
         };
+        let departure = candidate.departure().ok_or(GTError::MissingData(format!(
+            "Flight {} has no departure time.",
+            candidate.flight_id
+        )))?;
+
+        eprintln!("Selected flight {candidate}");
 
-        let Some(data_timestamp) = playback_btn.attr("data-timestamp") else {
-            return Err(GTError::HtmlSelection(
-                "No data-timestamp found in expected location.".to_string(),
-            ));
+        Ok((candidate.flight_id.clone(), departure.timestamp()))
+    }
+
+    /// Looks for a cached payload of this flight. Without a known flight id, a cached payload is
+    /// only trusted when offline, since another flight with the same code may have departed on
+    /// the same date.
+    fn find_cached(&self, flight_id: Option<&str>) -> GTResult<Option<String>> {
+        let Some(ref cache) = self.cache else {
+            return Ok(None);
         };
 
-        let url = format!(
-            "{}/flight-playback.json?flightId={}&timestamp={}",
-            Self::API_URL,
-            hex,
-            data_timestamp
-        );
+        if self.refresh || (flight_id.is_none() && !self.offline) {
+            return Ok(None);
+        }
+
+        let entries = cache.find(&self.flight_code, self.dod.date_naive(), flight_id)?;
 
-        return Ok(url);
+        match entries.as_slice() {
+            [] => Ok(None),
+            [entry] => {
+                eprintln!("Using cached flight data {entry}.");
+                Ok(Some(cache.load(entry)?))
+            }
+            _ => Err(GTError::InvalidData(format!(
+                "{} flights are cached for {} on {}. Use --flight-id to choose one.",
+                entries.len(),
+                self.flight_code,
+                self.dod.date_naive()
+            ))),
+        }
     }
 
-    fn download_flight_data(
-        &self,
-        client: &Client,
-        flight_data_url: String,
-    ) -> GTResult<FlightGeodata> {
+    fn download_flight_data(&self, client: &Client, flight_data_url: String) -> GTResult<String> {
         let response = client.get(flight_data_url).send()?.error_for_status()?;
-        let parsed_json: serde_json::Value = serde_json::from_str(response.text()?.as_str())?;
-
-        let parser = FlightRadar24JsonParser {};
-        let flight_data = parser.try_parse_geodata(parsed_json)?;
 
-        Ok(flight_data)
+        Ok(response.text()?)
     }
-}
 
-impl FlightDataProvider for FlightRadar24ApiProvider {
-    fn load_data(&self) -> GTResult<FlightGeodata> {
+    /// Returns the raw playback JSON, along with the flight id it was downloaded for when it
+    /// didn't come from the cache.
+    fn fetch_flight_data(&self) -> GTResult<(String, Option<String>)> {
+        if let Some(data) = self.find_cached(self.flight_id.as_deref())? {
+            return Ok((data, None));
+        }
+
+        if self.offline {
+            return Err(GTError::MissingData(format!(
+                "No cached flight data for {} on {}, and running offline.",
+                self.flight_code,
+                self.dod.date_naive()
+            )));
+        }
+
         let mut headers = reqwest::header::HeaderMap::new();
         headers.append(
             reqwest::header::USER_AGENT,
@@ -122,14 +331,50 @@ impl FlightDataProvider for FlightRadar24ApiProvider {
             .default_headers(headers)
             .build()?;
 
-        println!("Getting flight Id.");
+        eprintln!("Getting flight Id.");
+
+        let (flight_id, timestamp) = self.resolve_flight(&client)?;
+
+        if let Some(data) = self.find_cached(Some(&flight_id))? {
+            return Ok((data, None));
+        }
 
-        let flight_data_url = self.get_data_link(&client)?;
+        let flight_data_url = Self::get_playback_url(&flight_id, timestamp);
 
-        println!("Obtained url. Downloading data from '{flight_data_url}'.");
+        eprintln!("Obtained url. Downloading data from '{flight_data_url}'.");
 
         let data = self.download_flight_data(&client, flight_data_url)?;
 
-        Ok(data)
+        Ok((data, Some(flight_id)))
+    }
+}
+
+impl FlightDataProvider for FlightRadar24ApiProvider {
+    fn load_data(&self) -> GTResult<FlightGeodata> {
+        let (data, downloaded_id) = self.fetch_flight_data()?;
+        let parsed_json: serde_json::Value = serde_json::from_str(&data)?;
+
+        let parser = FlightRadar24JsonParser {};
+        let flight_data = parser.try_parse_geodata(parsed_json)?;
+
+        if self.read_only {
+            return Ok(flight_data);
+        }
+
+        // Only cached once it has parsed, so error or rate-limit responses are never reused.
+        if let (Some(cache), Some(flight_id)) = (&self.cache, downloaded_id) {
+            // The data is already in hand, so failing to cache it isn't worth failing the run.
+            match cache.store(&self.flight_code, self.dod.date_naive(), &flight_id, &data) {
+                Ok(path) => eprintln!("Cached flight data at '{}'.", path.display()),
+                Err(e) => eprintln!("Not caching flight data. Error: {e}"),
+            }
+        }
+
+        if let Some(ref path) = self.save_path {
+            std::fs::write(path, &data)?;
+            eprintln!("Saved flight data to '{}'.", path.display());
+        }
+
+        Ok(flight_data)
     }
 }
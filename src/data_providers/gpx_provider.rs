@@ -0,0 +1,37 @@
+use std::path::PathBuf;
+
+use crate::{
+    models::{flight_geodata::FlightGeodata, result::GTResult},
+    parsers::gpx_parser::GpxParser,
+};
+
+use super::FlightDataProvider;
+
+pub struct GpxFileProvider {
+    src_path: PathBuf,
+    flight_code: String,
+}
+
+impl FlightDataProvider for GpxFileProvider {
+    fn load_data(&self) -> GTResult<FlightGeodata> {
+        let contents = std::fs::read_to_string(&self.src_path)?;
+        let parser = GpxParser {};
+        let mut geodata = parser.try_parse_geodata(&contents)?;
+
+        // Phone and handheld logs rarely name their track after the flight.
+        if geodata.flight_code.is_empty() {
+            geodata.flight_code = self.flight_code.clone();
+        }
+
+        Ok(geodata)
+    }
+}
+
+impl GpxFileProvider {
+    pub fn new(src_path: PathBuf, flight_code: String) -> Self {
+        Self {
+            src_path,
+            flight_code,
+        }
+    }
+}
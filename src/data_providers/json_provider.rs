@@ -1,30 +1,46 @@
-use std::{fs::File, io::BufReader, path::PathBuf};
+use std::path::PathBuf;
 
-use serde_json::Value;
-
-use crate::parsers::json_parser::{FlightRadar24JsonParser, JsonParser};
+use crate::{
+    models::{flight_geodata::FlightGeodata, result::GTResult},
+    parsers::track_parser::{ParserRegistry, TrackSource},
+};
 
 use super::FlightDataProvider;
 
+/// Loads flight geodata from a track file, detecting its format from the file contents.
 pub struct FlightDataFileProvider {
     src_path: PathBuf,
+    flight_code: String,
 }
 
 impl FlightDataProvider for FlightDataFileProvider {
-    fn load_data(
-        &self,
-    ) -> crate::models::result::GTResult<crate::models::flight_geodata::FlightGeodata> {
-        let reader = BufReader::new(File::open(&self.src_path)?);
-        let json: Value = serde_json::from_reader(reader)?;
-        let parser = FlightRadar24JsonParser {};
-        let geodata = parser.try_parse_geodata(json)?;
+    fn load_data(&self) -> GTResult<FlightGeodata> {
+        let contents = std::fs::read(&self.src_path)?;
+        let src = TrackSource::new(&contents);
+        let registry = ParserRegistry::default();
+        let parser = registry.detect(&src)?;
+
+        eprintln!(
+            "Detected {} track data in '{}'.",
+            parser.format_name(),
+            self.src_path.display()
+        );
+
+        let mut geodata = parser.parse_track(src)?;
+
+        if geodata.flight_code.is_empty() {
+            geodata.flight_code = self.flight_code.clone();
+        }
 
         Ok(geodata)
     }
 }
 
 impl FlightDataFileProvider {
-    pub fn new(src_path: PathBuf) -> Self {
-        Self { src_path }
+    pub fn new(src_path: PathBuf, flight_code: String) -> Self {
+        Self {
+            src_path,
+            flight_code,
+        }
     }
 }
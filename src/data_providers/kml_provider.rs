@@ -0,0 +1,47 @@
+use std::{fs::File, io::BufReader, path::PathBuf};
+
+use crate::{
+    models::{flight_geodata::FlightGeodata, result::GTResult},
+    parsers::kml_parser::KmlParser,
+};
+
+use super::FlightDataProvider;
+
+pub struct KmlFileProvider {
+    src_path: PathBuf,
+    flight_code: String,
+}
+
+impl FlightDataProvider for KmlFileProvider {
+    fn load_data(&self) -> GTResult<FlightGeodata> {
+        let parser = KmlParser {};
+        let contents = if self.is_kmz() {
+            parser.extract_kmz_document(BufReader::new(File::open(&self.src_path)?))?
+        } else {
+            std::fs::read_to_string(&self.src_path)?
+        };
+
+        let mut geodata = parser.try_parse_geodata(&contents)?;
+
+        if geodata.flight_code.is_empty() {
+            geodata.flight_code = self.flight_code.clone();
+        }
+
+        Ok(geodata)
+    }
+}
+
+impl KmlFileProvider {
+    pub fn new(src_path: PathBuf, flight_code: String) -> Self {
+        Self {
+            src_path,
+            flight_code,
+        }
+    }
+
+    fn is_kmz(&self) -> bool {
+        self.src_path
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("kmz"))
+    }
+}
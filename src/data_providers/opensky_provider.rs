@@ -0,0 +1,160 @@
+use chrono::{DateTime, Utc};
+use reqwest::{blocking::Client, StatusCode};
+
+use crate::{
+    models::{
+        flight_geodata::FlightGeodata,
+        result::{GTError, GTResult},
+    },
+    parsers::{json_parser::JsonParser, opensky_parser::OpenSkyJsonParser},
+};
+
+use super::FlightDataProvider;
+
+/// Loads a flight track from the OpenSky Network REST API.
+pub struct OpenSkyApiProvider {
+    base_url: String,
+    icao24: String,
+    time: Option<DateTime<Utc>>,
+}
+
+impl OpenSkyApiProvider {
+    pub const DEFAULT_BASE_URL: &'static str = "https://opensky-network.org/api";
+
+    /// `time` may be any instant during the flight. Without it, the aircraft's live track is
+    /// requested instead.
+    pub fn new(icao24: String, time: Option<DateTime<Utc>>) -> Self {
+        Self {
+            base_url: Self::DEFAULT_BASE_URL.to_string(),
+            icao24: icao24.to_ascii_lowercase(),
+            time,
+        }
+    }
+
+    /// Overrides the API base URL, such as to point at a mirror or a local mock server.
+    pub fn with_base_url(mut self, base_url: String) -> Self {
+        self.base_url = base_url.trim_end_matches('/').to_string();
+        self
+    }
+
+    fn download_track(&self, client: &Client) -> GTResult<serde_json::Value> {
+        let time = self.time.map_or(0, |t| t.timestamp());
+        let url = format!("{}/tracks/all", self.base_url);
+
+        eprintln!(
+            "Downloading OpenSky track for aircraft '{}' from '{url}'.",
+            self.icao24
+        );
+
+        let response = client
+            .get(url)
+            .query(&[("icao24", self.icao24.clone()), ("time", time.to_string())])
+            .send()?;
+
+        if response.status() == StatusCode::NOT_FOUND {
+            return Err(GTError::MissingData(format!(
+                "OpenSky has no track for aircraft '{}' at time {time}.",
+                self.icao24
+            )));
+        }
+
+        let response = response.error_for_status()?;
+        let parsed_json = serde_json::from_str(response.text()?.as_str())?;
+
+        Ok(parsed_json)
+    }
+}
+
+impl FlightDataProvider for OpenSkyApiProvider {
+    fn load_data(&self) -> GTResult<FlightGeodata> {
+        let client = reqwest::blocking::ClientBuilder::new().build()?;
+
+        let parsed_json = self.download_track(&client)?;
+
+        let parser = OpenSkyJsonParser {};
+        let flight_data = parser.try_parse_geodata(parsed_json)?;
+
+        Ok(flight_data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::{BufRead, BufReader, Write},
+        net::TcpListener,
+        thread,
+    };
+
+    use chrono::DateTime;
+
+    use super::OpenSkyApiProvider;
+    use crate::data_providers::FlightDataProvider;
+
+    const FIXTURE: &str = include_str!("../../tests/fixtures/opensky_track.json");
+
+    /// Serves one HTTP response with `status` and `body`, returning the base URL to request and
+    /// a handle yielding the request line received.
+    fn serve_once(
+        status: &'static str,
+        body: &'static str,
+    ) -> (String, thread::JoinHandle<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let base_url = format!("http://{}/api/", listener.local_addr().unwrap());
+
+        let handle = thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream);
+
+            let mut request_line = String::new();
+            reader.read_line(&mut request_line).unwrap();
+            let mut header = String::new();
+            while reader.read_line(&mut header).unwrap() > 2 {
+                header.clear();
+            }
+
+            write!(
+                reader.get_mut(),
+                "HTTP/1.1 {status}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                body.len()
+            )
+            .unwrap();
+
+            request_line
+        });
+
+        (base_url, handle)
+    }
+
+    #[test]
+    fn loads_a_track_from_the_configured_base_url() {
+        let (base_url, server) = serve_once("200 OK", FIXTURE);
+
+        let data = OpenSkyApiProvider::new(
+            "4CA7B3".to_string(),
+            DateTime::from_timestamp(1704067300, 0),
+        )
+        .with_base_url(base_url)
+        .load_data()
+        .unwrap_or_else(|e| panic!("{e}"));
+
+        assert_eq!(data.flight_code, "RYR12AB");
+        assert_eq!(data.positions().len(), 4);
+        assert_eq!(
+            server.join().unwrap().trim_end(),
+            "GET /api/tracks/all?icao24=4ca7b3&time=1704067300 HTTP/1.1"
+        );
+    }
+
+    #[test]
+    fn missing_track_is_an_error() {
+        let (base_url, server) = serve_once("404 Not Found", "");
+
+        let result = OpenSkyApiProvider::new("4ca7b3".to_string(), None)
+            .with_base_url(base_url)
+            .load_data();
+
+        assert!(result.is_err());
+        server.join().unwrap();
+    }
+}
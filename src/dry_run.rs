@@ -0,0 +1,265 @@
+use std::{fmt::Display, path::PathBuf};
+
+use chrono::{DateTime, SecondsFormat, Utc};
+use clap::ValueEnum;
+use serde_json::json;
+
+use crate::{
+    image_geotagger::ImageLocation,
+    models::{flight_geodata::GeoPosition, result::GTResult},
+};
+
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, ValueEnum)]
+pub enum DryRunFormat {
+    Table,
+    Jsonl,
+}
+
+impl Display for DryRunFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let text = match self {
+            Self::Table => "table",
+            Self::Jsonl => "jsonl",
+        };
+        f.write_str(text)
+    }
+}
+
+/// The positions which would be written to each image, collected so they can be reviewed before
+/// any files are touched.
+#[derive(Default)]
+pub struct DryRunReport {
+    rows: Vec<(PathBuf, GTResult<ImageLocation>)>,
+}
+
+impl DryRunReport {
+    pub fn add(&mut self, relative_path: PathBuf, location: GTResult<ImageLocation>) {
+        self.rows.push((relative_path, location));
+    }
+
+    pub fn print(&self, format: DryRunFormat) {
+        let lines = match format {
+            DryRunFormat::Table => self.table_lines(),
+            DryRunFormat::Jsonl => self.jsonl_lines(),
+        };
+
+        for line in lines {
+            println!("{line}");
+        }
+    }
+
+    fn format_time(time: DateTime<Utc>) -> String {
+        time.to_rfc3339_opts(SecondsFormat::Millis, true)
+    }
+
+    fn table_lines(&self) -> Vec<String> {
+        let file_width = self
+            .rows
+            .iter()
+            .map(|(path, _)| path.display().to_string().len())
+            .max()
+            .unwrap_or(0)
+            .max("File".len());
+
+        let header = format!(
+            "{:<file_width$}  {:<24}  {:<24}  {:>10}  {:>11}  {:>6}  {:>8}  {:<24}  {:<24}  {:>9}",
+            "File",
+            "Captured (UTC)",
+            "Time source",
+            "Latitude",
+            "Longitude",
+            "Alt m",
+            "Error m",
+            "Before",
+            "After",
+            "Gap s"
+        );
+
+        let rows = self.rows.iter().map(|(path, location)| {
+            let path = path.display();
+
+            match location {
+                Ok(ImageLocation {
+                    timestamp,
+                    fix,
+                    gap_exceeded,
+                }) => format!(
+                    "{path:<file_width$}  {:<24}  {:<24}  {:>10.5}  {:>11.5}  {:>6}  {:>8.0}  {:<24}  {:<24}  {:>8.3}{}",
+                    Self::format_time(fix.position.timestamp),
+                    timestamp.source.to_string(),
+                    fix.position.latitude,
+                    fix.position.longitude,
+                    fix.position.altitude,
+                    fix.horizontal_error(),
+                    Self::format_time(fix.before.timestamp),
+                    Self::format_time(fix.after.timestamp),
+                    fix.gap().as_seconds_f64(),
+                    // Marks fixes interpolated across a gap longer than the maximum allowed.
+                    if *gap_exceeded { "!" } else { " " },
+                ),
+                Err(e) => format!("{path:<file_width$}  {e}"),
+            }
+        });
+
+        std::iter::once(header).chain(rows).collect()
+    }
+
+    fn position_json(position: &GeoPosition) -> serde_json::Value {
+        json!({
+            "time": Self::format_time(position.timestamp),
+            "latitude": position.latitude,
+            "longitude": position.longitude,
+            "altitude": position.altitude,
+            "speed": position.speed,
+            "heading": position.heading,
+            "vertical_speed": position.vertical_speed,
+        })
+    }
+
+    fn jsonl_lines(&self) -> Vec<String> {
+        self.rows
+            .iter()
+            .map(|(path, location)| match location {
+                Ok(ImageLocation {
+                    timestamp,
+                    fix,
+                    gap_exceeded,
+                }) => json!({
+                    "file": path.display().to_string(),
+                    "time_source": timestamp.source.to_string(),
+                    "position": Self::position_json(&fix.position),
+                    "horizontal_error_m": fix.horizontal_error(),
+                    "before": Self::position_json(&fix.before),
+                    "after": Self::position_json(&fix.after),
+                    "gap_seconds": fix.gap().as_seconds_f64(),
+                    "gap_exceeded": gap_exceeded,
+                }),
+                Err(e) => json!({
+                    "file": path.display().to_string(),
+                    "error": e.to_string(),
+                }),
+            })
+            .map(|line| line.to_string())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use chrono::{DateTime, TimeDelta, Utc};
+
+    use super::DryRunReport;
+    use crate::{
+        image_geotagger::ImageLocation,
+        models::{
+            camera_clock::{ImageTimestamp, TimestampSource},
+            flight_geodata::{GeoPosition, TrackFix},
+            result::GTError,
+        },
+    };
+
+    fn report() -> DryRunReport {
+        let position = |seconds, longitude| GeoPosition {
+            timestamp: DateTime::<Utc>::UNIX_EPOCH + TimeDelta::seconds(seconds),
+            latitude: 51.5,
+            longitude,
+            altitude: 1200,
+            speed: Some(250.0),
+            heading: None,
+            vertical_speed: None,
+        };
+        let fix = TrackFix {
+            position: position(30, -0.25),
+            before: position(0, -0.2),
+            after: position(60, -0.3),
+        };
+
+        let mut report = DryRunReport::default();
+        report.add(
+            PathBuf::from("sub/a.jpg"),
+            Ok(ImageLocation {
+                timestamp: ImageTimestamp {
+                    time: fix.position.timestamp,
+                    source: TimestampSource::OffsetTimeOriginal,
+                },
+                fix,
+                gap_exceeded: true,
+            }),
+        );
+        report.add(
+            PathBuf::from("b.jpg"),
+            Err(GTError::MissingExif("DateTimeOriginal".to_string())),
+        );
+        report
+    }
+
+    #[test]
+    fn jsonl_has_one_row_per_image() {
+        let lines = report().jsonl_lines();
+        assert_eq!(lines.len(), 2);
+
+        let row: serde_json::Value = serde_json::from_str(&lines[0]).unwrap();
+        let keys = row.as_object().unwrap().keys().collect::<Vec<_>>();
+        assert_eq!(
+            keys,
+            [
+                "after",
+                "before",
+                "file",
+                "gap_exceeded",
+                "gap_seconds",
+                "horizontal_error_m",
+                "position",
+                "time_source"
+            ]
+        );
+        assert_eq!(row["file"], "sub/a.jpg");
+        assert_eq!(row["time_source"], "OffsetTimeOriginal");
+        assert_eq!(row["position"]["time"], "1970-01-01T00:00:30.000Z");
+        assert_eq!(row["position"]["latitude"], 51.5);
+        assert_eq!(row["position"]["longitude"], -0.25);
+        assert_eq!(row["position"]["altitude"], 1200);
+        assert_eq!(row["position"]["speed"], 250.0);
+        assert!(row["position"]["heading"].is_null());
+        assert_eq!(row["before"]["time"], "1970-01-01T00:00:00.000Z");
+        assert_eq!(row["after"]["time"], "1970-01-01T00:01:00.000Z");
+        assert_eq!(row["gap_seconds"], 60.0);
+        assert_eq!(row["gap_exceeded"], true);
+        assert!(row["horizontal_error_m"].as_f64().is_some_and(|e| e > 0.0));
+
+        let error: serde_json::Value = serde_json::from_str(&lines[1]).unwrap();
+        assert_eq!(error["file"], "b.jpg");
+        assert_eq!(error["error"], "Missing EXIF error: DateTimeOriginal");
+    }
+
+    #[test]
+    fn table_columns_line_up_with_the_header() {
+        let lines = report().table_lines();
+        assert_eq!(lines.len(), 3);
+
+        let header = &lines[0];
+        let row = &lines[1];
+        let column = |line: &str, name: &str| {
+            let start = header
+                .find(name)
+                .unwrap_or_else(|| panic!("no {name} column"));
+            line[start..].split_whitespace().next().unwrap().to_string()
+        };
+
+        assert!(header.starts_with("File       Captured (UTC)"));
+        assert_eq!(column(row, "File"), "sub/a.jpg");
+        assert_eq!(column(row, "Captured"), "1970-01-01T00:00:30.000Z");
+        assert_eq!(column(row, "Time source"), "OffsetTimeOriginal");
+        assert_eq!(column(row, "Before"), "1970-01-01T00:00:00.000Z");
+        assert_eq!(column(row, "After"), "1970-01-01T00:01:00.000Z");
+        // Right-aligned columns end where their header does, and gap-flagged rows are marked.
+        assert!(row.ends_with("60.000!"));
+        assert_eq!(
+            row.find("51.50000").map(|i| i + 8),
+            header.find("Latitude").map(|i| i + 8)
+        );
+        assert!(lines[2].starts_with("b.jpg      Missing EXIF error"));
+    }
+}
@@ -0,0 +1,247 @@
+use std::{fmt::Display, path::PathBuf};
+
+use chrono::NaiveDate;
+
+use crate::models::result::{GTError, GTResult};
+
+/// A cached FlightRadar24 playback payload.
+pub struct CacheEntry {
+    pub flight_code: String,
+    pub dod: NaiveDate,
+    pub flight_id: String,
+    pub path: PathBuf,
+}
+
+impl Display for CacheEntry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} departing {} [{}] ({})",
+            self.flight_code,
+            self.dod,
+            self.flight_id,
+            self.path.display()
+        )
+    }
+}
+
+/// On-disk store of raw FlightRadar24 playback JSON, keyed by flight code, date of departure and
+/// flight id.
+pub struct FlightCache {
+    dir: PathBuf,
+}
+
+impl FlightCache {
+    const APP_DIR: &'static str = "flight-image-geotagger";
+    const DATE_FORMAT: &'static str = "%Y-%m-%d";
+
+    pub fn new(dir: PathBuf) -> Self {
+        Self { dir }
+    }
+
+    /// Opens the cache under `$XDG_CACHE_HOME`, or `~/.cache` when that isn't set.
+    pub fn open_default() -> GTResult<Self> {
+        let cache_home = std::env::var_os("XDG_CACHE_HOME")
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".cache")))
+            .ok_or(GTError::MissingData(
+                "Neither XDG_CACHE_HOME nor HOME is set, so there is no cache directory."
+                    .to_string(),
+            ))?;
+
+        Ok(Self::new(cache_home.join(Self::APP_DIR)))
+    }
+
+    pub fn dir(&self) -> &PathBuf {
+        &self.dir
+    }
+
+    /// Builds the path of an entry, refusing names which could escape the cache directory or
+    /// couldn't be split apart again by `parse_entry`.
+    fn entry_path(&self, flight_code: &str, dod: NaiveDate, flight_id: &str) -> GTResult<PathBuf> {
+        let is_valid = |name: &str, allow_underscore: bool| {
+            !name.is_empty()
+                && name.chars().all(|c| {
+                    c.is_ascii_alphanumeric() || c == '-' || (allow_underscore && c == '_')
+                })
+        };
+
+        if !is_valid(flight_code, true) {
+            return Err(GTError::InvalidData(format!(
+                "Can't cache flight code '{flight_code}', which should only contain letters, \
+                 digits, '-' and '_'."
+            )));
+        }
+        if !is_valid(flight_id, false) {
+            return Err(GTError::InvalidData(format!(
+                "Can't cache flight id '{flight_id}', which should only contain letters, digits \
+                 and '-'."
+            )));
+        }
+
+        Ok(self.dir.join(format!(
+            "{}_{}_{}.json",
+            flight_code.to_ascii_uppercase(),
+            dod.format(Self::DATE_FORMAT),
+            flight_id.to_ascii_lowercase()
+        )))
+    }
+
+    fn parse_entry(path: PathBuf) -> Option<CacheEntry> {
+        if path.extension()? != "json" {
+            return None;
+        }
+
+        // Split from the right, since flight codes may themselves contain underscores.
+        let stem = path.file_stem()?.to_str()?;
+        let mut parts = stem.rsplitn(3, '_');
+        let flight_id = parts.next()?.to_string();
+        let dod = NaiveDate::parse_from_str(parts.next()?, Self::DATE_FORMAT).ok()?;
+        let flight_code = parts.next()?.to_string();
+
+        Some(CacheEntry {
+            flight_code,
+            dod,
+            flight_id,
+            path,
+        })
+    }
+
+    /// Lists every cached payload. A missing cache directory is treated as an empty cache.
+    pub fn entries(&self) -> GTResult<Vec<CacheEntry>> {
+        if !self.dir.is_dir() {
+            return Ok(Vec::new());
+        }
+
+        let mut entries = Vec::new();
+        for entry_res in std::fs::read_dir(&self.dir)? {
+            if let Some(entry) = Self::parse_entry(entry_res?.path()) {
+                entries.push(entry);
+            }
+        }
+
+        entries.sort_by(|a, b| {
+            (a.dod, &a.flight_code, &a.flight_id).cmp(&(b.dod, &b.flight_code, &b.flight_id))
+        });
+
+        Ok(entries)
+    }
+
+    /// Finds the cached payloads for a flight code on a date, optionally narrowed to one flight id.
+    pub fn find(
+        &self,
+        flight_code: &str,
+        dod: NaiveDate,
+        flight_id: Option<&str>,
+    ) -> GTResult<Vec<CacheEntry>> {
+        Ok(self
+            .entries()?
+            .into_iter()
+            .filter(|e| {
+                e.flight_code.eq_ignore_ascii_case(flight_code)
+                    && e.dod == dod
+                    && flight_id.is_none_or(|id| e.flight_id.eq_ignore_ascii_case(id))
+            })
+            .collect())
+    }
+
+    pub fn load(&self, entry: &CacheEntry) -> GTResult<String> {
+        Ok(std::fs::read_to_string(&entry.path)?)
+    }
+
+    pub fn store(
+        &self,
+        flight_code: &str,
+        dod: NaiveDate,
+        flight_id: &str,
+        contents: &str,
+    ) -> GTResult<PathBuf> {
+        let path = self.entry_path(flight_code, dod, flight_id)?;
+
+        std::fs::create_dir_all(&self.dir)?;
+
+        // Written to a temporary file first, so an interrupted write never leaves a truncated entry.
+        let temp_path = path.with_extension("json.tmp");
+        if let Err(e) = std::fs::write(&temp_path, contents) {
+            let _ = std::fs::remove_file(&temp_path);
+            return Err(e.into());
+        }
+        std::fs::rename(temp_path, &path)?;
+
+        Ok(path)
+    }
+
+    /// Deletes every cached payload, returning how many were removed.
+    pub fn clear(&self) -> GTResult<usize> {
+        let entries = self.entries()?;
+
+        for entry in &entries {
+            std::fs::remove_file(&entry.path)?;
+        }
+
+        Ok(entries.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use chrono::NaiveDate;
+
+    use super::FlightCache;
+
+    #[test]
+    fn entries_are_split_from_the_right() {
+        let entry =
+            FlightCache::parse_entry(PathBuf::from("/cache/PRIVATE_1_2024-01-01_3a1b2c.json"))
+                .expect("entry should parse");
+
+        assert_eq!(entry.flight_code, "PRIVATE_1");
+        assert_eq!(entry.dod, NaiveDate::from_ymd_opt(2024, 1, 1).unwrap());
+        assert_eq!(entry.flight_id, "3a1b2c");
+    }
+
+    #[test]
+    fn stored_entries_can_be_found() {
+        let dir = std::env::temp_dir().join(format!("flight-cache-test-{}", std::process::id()));
+        let cache = FlightCache::new(dir.clone());
+        let dod = NaiveDate::from_ymd_opt(2024, 1, 1).unwrap();
+
+        cache
+            .store("ba_123", dod, "3A1B2C", "{}")
+            .unwrap_or_else(|e| panic!("{e}"));
+        let found = cache
+            .find("BA_123", dod, Some("3a1b2c"))
+            .unwrap_or_else(|e| panic!("{e}"));
+
+        assert_eq!(found.len(), 1);
+        assert_eq!(
+            cache.load(&found[0]).unwrap_or_else(|e| panic!("{e}")),
+            "{}"
+        );
+        assert!(!dir.join("BA_123_2024-01-01_3a1b2c.json.tmp").exists());
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn names_which_would_escape_the_cache_are_rejected() {
+        let dir = std::env::temp_dir().join(format!("flight-cache-names-{}", std::process::id()));
+        let cache = FlightCache::new(dir.clone());
+        let dod = NaiveDate::from_ymd_opt(2024, 1, 1).unwrap();
+        let store = |flight_code, flight_id| cache.store(flight_code, dod, flight_id, "{}");
+
+        assert!(store("../BA123", "3a1b2c").is_err());
+        assert!(store("BA/123", "3a1b2c").is_err());
+        assert!(store("BA123", "../../3a1b2c").is_err());
+        assert!(store("BA123", "3a1b_2c").is_err());
+        assert!(store("", "3a1b2c").is_err());
+        assert!(!dir.exists());
+
+        assert!(store("BA_123", "3a1b-2c").is_ok());
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -1,32 +1,167 @@
 use std::{
+    fmt::Display,
     fs::File,
     path::{Path, PathBuf},
 };
 
-use chrono::{DateTime, FixedOffset, TimeZone, Utc};
+use chrono::{
+    DateTime, FixedOffset, NaiveDate, NaiveDateTime, NaiveTime, TimeDelta, TimeZone, Utc,
+};
+use clap::ValueEnum;
 use exif::{Exif, Field, In, Tag, Value};
 use img_parts::{jpeg::Jpeg, ImageEXIF};
 
 use crate::models::{
-    flight_geodata::FlightGeodata,
+    camera_clock::{CameraClock, ImageTimestamp, TimestampSource},
+    flight_geodata::{FlightGeodata, TrackFix},
     result::{GTError, GTResult},
 };
 
+/// Where geotagged images are written.
+pub enum ImageOutput {
+    /// Copies are written to this directory, mirroring the layout of the images directory.
+    Directory(PathBuf),
+    /// The original files are replaced, optionally keeping a copy of each at the original path
+    /// plus `backup_suffix`.
+    InPlace { backup_suffix: Option<String> },
+}
+
+/// What to do with an image whose position falls in a gap in the flight track longer than the
+/// maximum allowed.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, ValueEnum)]
+pub enum GapAction {
+    /// Leave the image untagged.
+    Skip,
+    /// Tag the image anyway, but report it as flagged in the summary and `--report`.
+    Flag,
+}
+
+impl Display for GapAction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let text = match self {
+            Self::Skip => "skip",
+            Self::Flag => "flag",
+        };
+        f.write_str(text)
+    }
+}
+
+/// Where and when an image was taken.
+#[derive(Clone, Copy, Debug)]
+pub struct ImageLocation {
+    pub timestamp: ImageTimestamp,
+    pub fix: TrackFix,
+    /// Whether the fix was interpolated across a gap longer than the maximum allowed.
+    pub gap_exceeded: bool,
+}
+
 pub struct ImageGeotagger {
-    output_dir: PathBuf,
+    output: ImageOutput,
     flight_data: FlightGeodata,
+    time_offset: TimeDelta,
+    camera_clock: CameraClock,
+    max_gap: Option<TimeDelta>,
+    gap_action: GapAction,
 }
 
 impl ImageGeotagger {
-    pub fn new(output_dir: PathBuf, data: FlightGeodata) -> Self {
+    /// GPS fixes can lag the shutter, so the UTC offset derived from them is rounded to the
+    /// nearest whole time zone step.
+    const GPS_OFFSET_STEP_MINUTES: i64 = 15;
+
+    /// Appended to an image's path while its geotagged copy is written, before being renamed
+    /// over it when tagging in place.
+    pub const TEMP_SUFFIX: &'static str = ".geotagging";
+
+    pub fn new(output: ImageOutput, data: FlightGeodata) -> Self {
         Self {
-            output_dir,
+            output,
             flight_data: data,
+            time_offset: TimeDelta::zero(),
+            camera_clock: CameraClock::default(),
+            max_gap: None,
+            gap_action: GapAction::Skip,
+        }
+    }
+
+    /// Treats images which fall between track points further apart than `max_gap` according to
+    /// `gap_action`.
+    pub fn with_max_gap(mut self, max_gap: Option<TimeDelta>, gap_action: GapAction) -> Self {
+        self.max_gap = max_gap;
+        self.gap_action = gap_action;
+        self
+    }
+
+    /// Corrects the camera clock by adding `time_offset` to every image timestamp before it is
+    /// looked up in the flight data.
+    pub fn with_time_offset(mut self, time_offset: TimeDelta) -> Self {
+        self.time_offset = time_offset;
+        self
+    }
+
+    /// Sets the camera's time zone, used for images which don't record a UTC offset.
+    pub fn with_camera_clock(mut self, camera_clock: CameraClock) -> Self {
+        self.camera_clock = camera_clock;
+        self
+    }
+
+    /// Reads the capture times of the given images. Files which aren't images or have no usable
+    /// timestamp are skipped.
+    pub fn read_image_timestamps(
+        image_paths: &[PathBuf],
+        camera_clock: &CameraClock,
+    ) -> Vec<DateTime<Utc>> {
+        image_paths
+            .iter()
+            .filter_map(|path| Self::read_image_timestamp(path, camera_clock).ok())
+            .map(|timestamp| timestamp.time)
+            .collect()
+    }
+
+    pub fn read_image_timestamp(
+        image_path: &Path,
+        camera_clock: &CameraClock,
+    ) -> GTResult<ImageTimestamp> {
+        let img_file = std::fs::read(image_path)?;
+        let jpeg = Jpeg::from_bytes(img_file.into())?;
+        let exif = Self::load_image_exif(&jpeg)?;
+
+        Self::get_image_timestamp(&exif, camera_clock)
+    }
+
+    /// Works out where the image at `image_path` was taken, without modifying it.
+    pub fn locate_image(&self, image_path: &Path) -> GTResult<ImageLocation> {
+        let img_file = std::fs::read(image_path)?;
+        let jpeg = Jpeg::from_bytes(img_file.into())?;
+        let exif = Self::load_image_exif(&jpeg)?;
+
+        self.locate(&exif)
+    }
+
+    fn locate(&self, exif: &Exif) -> GTResult<ImageLocation> {
+        let mut timestamp = Self::get_image_timestamp(exif, &self.camera_clock)?;
+        timestamp.time += self.time_offset;
+
+        let fix = self.flight_data.get_fix_from_datetime(timestamp.time)?;
+        let gap_exceeded = self.max_gap.is_some_and(|max_gap| fix.gap() > max_gap);
+
+        if gap_exceeded && self.gap_action == GapAction::Skip {
+            return Err(GTError::OutOfRange(format!(
+                "Flight track has a {}s gap around {}, which is longer than the maximum allowed.",
+                fix.gap().num_seconds(),
+                timestamp.time
+            )));
         }
+
+        Ok(ImageLocation {
+            timestamp,
+            fix,
+            gap_exceeded,
+        })
     }
 
-    fn load_image_exif(&self, jpeg: &Jpeg) -> GTResult<Exif> {
-        let exif_raw = jpeg.exif().ok_or(GTError::MissingData(
+    fn load_image_exif(jpeg: &Jpeg) -> GTResult<Exif> {
+        let exif_raw = jpeg.exif().ok_or(GTError::MissingExif(
             "No EXIF data found in image".to_string(),
         ))?;
         let exif_reader = exif::Reader::new();
@@ -35,31 +170,24 @@ impl ImageGeotagger {
         Ok(exif)
     }
 
-    fn get_image_timestamp(&self, exif: &Exif) -> GTResult<DateTime<Utc>> {
-        let datetime_original = exif
-            .get_field(Tag::DateTimeOriginal, In::PRIMARY)
-            .ok_or(GTError::MissingData("DateTimeOriginal".to_string()))?;
-
-        let offset_original = exif
-            .get_field(Tag::OffsetTimeOriginal, In::PRIMARY)
-            .ok_or(GTError::MissingData("OffsetTimeOriginal".to_string()))?;
-
-        let Value::Ascii(ref datetime_original) = datetime_original.value else {
-            return Err(GTError::MissingData(
-                "DateTimeOriginal not formatted correctly.".to_string(),
-            ));
+    fn get_ascii_field(exif: &Exif, tag: Tag) -> Option<&[u8]> {
+        let Value::Ascii(ref value) = exif.get_field(tag, In::PRIMARY)?.value else {
+            return None;
         };
 
-        let Value::Ascii(ref offset_original) = offset_original.value else {
-            return Err(GTError::MissingData(
-                "OffsetTimeOriginal not formatted correctly.".to_string(),
-            ));
-        };
+        value.first().map(Vec::as_slice)
+    }
 
-        let mut datetime_original = exif::DateTime::from_ascii(&datetime_original[0])?;
-        datetime_original.parse_offset(&offset_original[0])?;
+    fn get_local_datetime(exif: &Exif) -> GTResult<NaiveDateTime> {
+        exif.get_field(Tag::DateTimeOriginal, In::PRIMARY)
+            .ok_or(GTError::MissingExif("DateTimeOriginal".to_string()))?;
 
-        let naive_date = chrono::NaiveDate::from_ymd_opt(
+        let datetime_original = Self::get_ascii_field(exif, Tag::DateTimeOriginal).ok_or(
+            GTError::MissingData("DateTimeOriginal not formatted correctly.".to_string()),
+        )?;
+        let datetime_original = exif::DateTime::from_ascii(datetime_original)?;
+
+        let naive_date = NaiveDate::from_ymd_opt(
             datetime_original.year.into(),
             datetime_original.month.into(),
             datetime_original.day.into(),
@@ -67,7 +195,7 @@ impl ImageGeotagger {
         .ok_or(GTError::InvalidData(
             "Invalid date initialisation".to_string(),
         ))?;
-        let naive_time = chrono::NaiveTime::from_hms_opt(
+        let naive_time = NaiveTime::from_hms_opt(
             datetime_original.hour.into(),
             datetime_original.minute.into(),
             datetime_original.second.into(),
@@ -76,63 +204,259 @@ impl ImageGeotagger {
             "Invalid time initialisation".to_string(),
         ))?;
 
-        let naive_datetime = chrono::NaiveDateTime::new(naive_date, naive_time);
-        let adjusted_datetime = datetime_original
-            .offset
-            .and_then(|min| FixedOffset::east_opt((min * 60).into()))
-            .and_then(|offset| offset.from_local_datetime(&naive_datetime).single())
+        let naive_datetime = NaiveDateTime::new(naive_date, naive_time);
+
+        Ok(naive_datetime + Self::get_subsec(exif).unwrap_or_default())
+    }
+
+    /// Reads `SubSecTimeOriginal`, the decimal fraction of a second to add to `DateTimeOriginal`.
+    fn get_subsec(exif: &Exif) -> Option<TimeDelta> {
+        let subsec = Self::get_ascii_field(exif, Tag::SubSecTimeOriginal)?;
+        let digits = std::str::from_utf8(subsec).ok()?.trim();
+
+        if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+
+        let nanos = format!("{:0<9}", &digits[..digits.len().min(9)]);
+
+        Some(TimeDelta::nanoseconds(nanos.parse().ok()?))
+    }
+
+    fn get_offset_field(exif: &Exif, tag: Tag) -> Option<FixedOffset> {
+        let offset = Self::get_ascii_field(exif, tag)?;
+
+        // exif::DateTime only parses offsets onto a date, so borrow a placeholder one.
+        let mut datetime = exif::DateTime::from_ascii(b"2000:01:01 00:00:00").ok()?;
+        datetime.parse_offset(offset).ok()?;
+
+        FixedOffset::east_opt(i32::from(datetime.offset?) * 60)
+    }
+
+    /// Derives the UTC offset of `local` from the GPS date and time tags, which are always in UTC,
+    /// unless they were written by an earlier run of this tool and so already include its
+    /// corrections.
+    fn get_gps_offset(exif: &Exif, local: NaiveDateTime) -> Option<FixedOffset> {
+        if let Some(Value::Undefined(method, _)) = exif
+            .get_field(Tag::GPSProcessingMethod, In::PRIMARY)
+            .map(|f| &f.value)
+        {
+            if method.ends_with(TrackFix::GPS_PROCESSING_METHOD) {
+                return None;
+            }
+        }
+
+        let date = Self::get_ascii_field(exif, Tag::GPSDateStamp)?;
+        let date = NaiveDate::parse_from_str(std::str::from_utf8(date).ok()?, "%Y:%m:%d").ok()?;
+
+        let Value::Rational(ref time) = exif.get_field(Tag::GPSTimeStamp, In::PRIMARY)?.value
+        else {
+            return None;
+        };
+        let [hours, minutes, seconds] = time.as_slice() else {
+            return None;
+        };
+        let seconds = hours.to_f64() * 3600.0 + minutes.to_f64() * 60.0 + seconds.to_f64();
+        let gps_time =
+            date.and_hms_opt(0, 0, 0)? + TimeDelta::milliseconds((seconds * 1000.0) as i64);
+
+        let step = Self::GPS_OFFSET_STEP_MINUTES * 60;
+        let offset = (local - gps_time).num_seconds();
+        let offset = ((offset as f64 / step as f64).round() as i64) * step;
+
+        FixedOffset::east_opt(i32::try_from(offset).ok()?)
+    }
+
+    fn localise(
+        local: NaiveDateTime,
+        offset: FixedOffset,
+        source: TimestampSource,
+    ) -> GTResult<ImageTimestamp> {
+        let time = offset
+            .from_local_datetime(&local)
+            .single()
             .ok_or(GTError::Conversion(
                 "Failed to parse timezone offset.".to_string(),
             ))?;
 
-        Ok(adjusted_datetime.with_timezone(&Utc))
+        Ok(ImageTimestamp {
+            time: time.with_timezone(&Utc),
+            source,
+        })
     }
 
+    /// Works out the UTC capture time of an image. Since `DateTimeOriginal` is in the camera's
+    /// local time, its UTC offset is taken from the first of the EXIF offset tags, the
+    /// user-supplied camera clock, and the GPS timestamp which is available.
+    fn get_image_timestamp(exif: &Exif, camera_clock: &CameraClock) -> GTResult<ImageTimestamp> {
+        let local = Self::get_local_datetime(exif)?;
+
+        let offset_tags = [
+            (Tag::OffsetTimeOriginal, TimestampSource::OffsetTimeOriginal),
+            (Tag::OffsetTime, TimestampSource::OffsetTime),
+            (
+                Tag::OffsetTimeDigitized,
+                TimestampSource::OffsetTimeDigitized,
+            ),
+        ];
+        for (tag, source) in offset_tags {
+            if let Some(offset) = Self::get_offset_field(exif, tag) {
+                return Self::localise(local, offset, source);
+            }
+        }
+
+        if let Some(tz) = camera_clock.tz {
+            // Times repeated when the clocks go back are ambiguous, so take the earlier one.
+            let time = tz
+                .from_local_datetime(&local)
+                .earliest()
+                .ok_or(GTError::InvalidData(format!(
+                    "{local} does not exist in time zone {tz}."
+                )))?;
+
+            return Ok(ImageTimestamp {
+                time: time.with_timezone(&Utc),
+                source: TimestampSource::CameraTz(tz),
+            });
+        }
+
+        if let Some(offset) = camera_clock.utc_offset {
+            return Self::localise(local, offset, TimestampSource::CameraUtcOffset(offset));
+        }
+
+        // The camera's GPS fix may be hours stale in airplane mode, so it ranks below the clock
+        // settings given by the user.
+        if let Some(offset) = Self::get_gps_offset(exif, local) {
+            return Self::localise(local, offset, TimestampSource::GpsTimestamp);
+        }
+
+        Err(GTError::MissingData(
+            "OffsetTimeOriginal (or any other UTC offset). Set --camera-tz or --camera-utc-offset."
+                .to_string(),
+        ))
+    }
+
+    /// Replaces every GPS tag this tool writes with `new_fields`, so that none from the camera or
+    /// an earlier run are left describing a different position. Other GPS tags, such as
+    /// GPSImgDirection, are kept.
     fn build_new_exif<'a>(&self, exif: &'a Exif, new_fields: &'a [Field]) -> Vec<&'a Field> {
         exif.fields()
-            .filter(|f| match f.tag {
-                Tag::GPSAltitudeRef
-                | Tag::GPSAltitude
-                | Tag::GPSLatitudeRef
-                | Tag::GPSLatitude
-                | Tag::GPSLongitudeRef
-                | Tag::GPSLongitude => false,
-                _ => true,
-            })
+            .filter(|f| !TrackFix::GPS_TAGS.contains(&f.tag))
             .chain(new_fields)
             .collect::<Vec<_>>()
     }
 
-    fn save_new_image(&self, image_path: &Path, jpeg: Jpeg) -> GTResult<()> {
-        let output_path = self.output_dir.join(
-            image_path
-                .file_name()
-                .expect("Filename should already be present."),
-        );
+    fn save_new_image(
+        &self,
+        image_path: &Path,
+        relative_path: &Path,
+        jpeg: Jpeg,
+        log: &mut Vec<String>,
+    ) -> GTResult<()> {
+        match self.output {
+            ImageOutput::Directory(ref output_dir) => {
+                let output_path = output_dir.join(relative_path);
+
+                log.push(format!("Saving image to {}", output_path.display()));
+
+                std::fs::create_dir_all(
+                    output_path
+                        .parent()
+                        .expect("Output path should be inside the output directory."),
+                )?;
 
-        println!("\nSaving image to {}", output_path.display());
+                Self::write_image(&output_path, jpeg)
+            }
+            ImageOutput::InPlace { ref backup_suffix } => {
+                if let Some(suffix) = backup_suffix {
+                    let backup_path = Self::with_suffix(image_path, suffix);
 
-        std::fs::create_dir_all(&self.output_dir)?;
+                    // An existing backup is from an earlier run, and holds the real original.
+                    if backup_path.exists() {
+                        log.push(format!("Keeping existing backup {}", backup_path.display()));
+                    } else {
+                        log.push(format!("Backing up image to {}", backup_path.display()));
+                        std::fs::copy(image_path, backup_path)?;
+                    }
+                }
+
+                log.push(format!("Overwriting image {}", image_path.display()));
+
+                // Writing to a sibling file and renaming it over the original means the original
+                // is never left half-written.
+                let temp_path = Self::with_suffix(image_path, Self::TEMP_SUFFIX);
+                let written = Self::write_image(&temp_path, jpeg).and_then(|()| {
+                    // Otherwise the rename would leave the image with default permissions.
+                    let permissions = std::fs::metadata(image_path)?.permissions();
+                    Ok(std::fs::set_permissions(&temp_path, permissions)?)
+                });
+                if let Err(e) = written {
+                    let _ = std::fs::remove_file(&temp_path);
+                    return Err(e);
+                }
+                std::fs::rename(temp_path, image_path)?;
+
+                Ok(())
+            }
+        }
+    }
 
+    fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+        let mut path = path.as_os_str().to_owned();
+        path.push(suffix);
+        PathBuf::from(path)
+    }
+
+    fn write_image(output_path: &Path, jpeg: Jpeg) -> GTResult<()> {
         let output_file = File::create(output_path)?;
-        let output_writer = std::io::BufWriter::new(output_file);
+        let mut output_writer = std::io::BufWriter::new(output_file);
+
+        jpeg.encoder().write_to(&mut output_writer)?;
+        output_writer
+            .into_inner()
+            .map_err(|e| e.into_error())?
+            .sync_all()?;
 
-        jpeg.encoder().write_to(output_writer)?;
         Ok(())
     }
 
-    pub fn apply_gps_data(&self, image_path: &Path) -> GTResult<()> {
+    /// Geotags the image at `image_path`. When writing to an output directory, it is saved there
+    /// at `relative_path`.
+    ///
+    /// Progress is added to `log` rather than printed, so that the output for each image stays
+    /// together when several are processed at once. Returns where the image was placed.
+    pub fn apply_gps_data(
+        &self,
+        image_path: &Path,
+        relative_path: &Path,
+        log: &mut Vec<String>,
+    ) -> GTResult<ImageLocation> {
         let img_file = std::fs::read(image_path)?;
         let mut jpeg = Jpeg::from_bytes(img_file.into())?;
 
-        let exif = self.load_image_exif(&jpeg)?;
+        let exif = Self::load_image_exif(&jpeg)?;
 
-        let timestamp = self.get_image_timestamp(&exif)?;
+        let location = self.locate(&exif)?;
+        log.push(format!(
+            "Image taken at {} (offset from {}).",
+            location.timestamp.time, location.timestamp.source
+        ));
 
-        println!("Done.\nGetting new GPS metadata.");
-        let new_fields = self.flight_data.get_gps_exif_from_datetime(timestamp)?;
+        if location.gap_exceeded {
+            log.push(format!(
+                "Warning: position is interpolated across a {}s gap in the flight track.",
+                location.fix.gap().num_seconds()
+            ));
+        }
 
-        println!("Building new EXIF.");
+        log.push("Getting new GPS metadata.".to_string());
+        log.push(format!(
+            "Lower position: {:?}\nInterpolated position: {:?}\nHigher position: {:?}",
+            location.fix.before, location.fix.position, location.fix.after
+        ));
+        let new_fields = location.fix.get_gps_exif()?;
+
+        log.push("Building new EXIF.".to_string());
 
         let fields_to_write = self.build_new_exif(&exif, &new_fields);
         let mut buffer = std::io::Cursor::new(Vec::new());
@@ -142,11 +466,259 @@ impl ImageGeotagger {
         }
         writer.write(&mut buffer, false)?;
 
-        println!("Done.");
-
         jpeg.set_exif(Some(buffer.into_inner().into()));
-        self.save_new_image(image_path, jpeg)?;
+        self.save_new_image(image_path, relative_path, jpeg, log)?;
 
-        Ok(())
+        Ok(location)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use chrono::{DateTime, FixedOffset, TimeZone, Utc};
+    use exif::{Context, Exif, Field, In, Rational, Tag, Value};
+    use img_parts::jpeg::Jpeg;
+
+    use super::{ImageGeotagger, ImageOutput};
+    use crate::models::{
+        camera_clock::CameraClock,
+        flight_geodata::{FlightGeodata, GeoPosition, TrackFix},
+    };
+
+    fn ascii_field(tag: Tag, value: &str) -> Field {
+        Field {
+            tag,
+            ifd_num: In::PRIMARY,
+            value: Value::Ascii(vec![value.as_bytes().to_vec()]),
+        }
+    }
+
+    fn exif_from(fields: &[Field]) -> Exif {
+        let mut writer = exif::experimental::Writer::new();
+        for field in fields {
+            writer.push_field(field);
+        }
+        let mut buffer = std::io::Cursor::new(Vec::new());
+        writer
+            .write(&mut buffer, false)
+            .unwrap_or_else(|e| panic!("{e}"));
+
+        exif::Reader::new()
+            .read_raw(buffer.into_inner())
+            .unwrap_or_else(|e| panic!("{e}"))
+    }
+
+    /// An image taken at 12:00 local time, with a GPS stamp of 10:00 UTC.
+    fn gps_stamped_fields(processing_method: &[u8]) -> Vec<Field> {
+        vec![
+            ascii_field(Tag::DateTimeOriginal, "2024:01:01 12:00:00"),
+            ascii_field(Tag::GPSDateStamp, "2024:01:01"),
+            Field {
+                tag: Tag::GPSTimeStamp,
+                ifd_num: In::PRIMARY,
+                value: Value::Rational(vec![
+                    Rational::from((10, 1)),
+                    Rational::from((0, 1)),
+                    Rational::from((0, 1)),
+                ]),
+            },
+            Field {
+                tag: Tag::GPSProcessingMethod,
+                ifd_num: In::PRIMARY,
+                value: Value::Undefined([b"ASCII\0\0\0", processing_method].concat(), 0),
+            },
+        ]
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn tagging_in_place_keeps_the_original_permissions() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = std::env::temp_dir().join(format!("geotagger-in-place-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let image_path = dir.join("a.jpg");
+        std::fs::write(&image_path, b"\xFF\xD8\xFF\xD9").unwrap();
+        std::fs::set_permissions(&image_path, std::fs::Permissions::from_mode(0o640)).unwrap();
+
+        let jpeg = Jpeg::from_bytes(std::fs::read(&image_path).unwrap().into())
+            .unwrap_or_else(|e| panic!("{e}"));
+        let position = GeoPosition {
+            timestamp: DateTime::<Utc>::UNIX_EPOCH,
+            latitude: 0.0,
+            longitude: 0.0,
+            altitude: 0,
+            speed: None,
+            heading: None,
+            vertical_speed: None,
+        };
+        let data = FlightGeodata::new("TEST1".to_string(), vec![position])
+            .unwrap_or_else(|e| panic!("{e}"));
+        ImageGeotagger::new(
+            ImageOutput::InPlace {
+                backup_suffix: None,
+            },
+            data,
+        )
+        .save_new_image(&image_path, "a.jpg".as_ref(), jpeg, &mut Vec::new())
+        .unwrap_or_else(|e| panic!("{e}"));
+
+        let mode = std::fs::metadata(&image_path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o640);
+        assert!(!ImageGeotagger::with_suffix(&image_path, ImageGeotagger::TEMP_SUFFIX).exists());
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    fn subsec(value: &str) -> Option<i64> {
+        let exif = exif_from(&[ascii_field(Tag::SubSecTimeOriginal, value)]);
+
+        ImageGeotagger::get_subsec(&exif).and_then(|subsec| subsec.num_nanoseconds())
+    }
+
+    #[test]
+    fn subsec_digits_are_a_decimal_fraction() {
+        assert_eq!(subsec("5"), Some(500_000_000));
+        assert_eq!(subsec("050"), Some(50_000_000));
+        assert_eq!(subsec(" 25 "), Some(250_000_000));
+        // Digits past nanoseconds are dropped.
+        assert_eq!(subsec("1234567891"), Some(123_456_789));
+    }
+
+    #[test]
+    fn invalid_subsec_is_ignored() {
+        assert_eq!(subsec("5a"), None);
+        assert_eq!(subsec("-5"), None);
+        assert_eq!(subsec(""), None);
+    }
+
+    #[test]
+    fn burst_shots_in_the_same_second_get_distinct_positions() {
+        let track = [(0, 0.0), (10, 0.1)]
+            .map(|(seconds, longitude)| GeoPosition {
+                timestamp: DateTime::<Utc>::UNIX_EPOCH + chrono::TimeDelta::seconds(seconds),
+                latitude: 0.0,
+                longitude,
+                altitude: 10_000,
+                speed: None,
+                heading: None,
+                vertical_speed: None,
+            })
+            .to_vec();
+        let flight =
+            FlightGeodata::new("TEST1".to_string(), track).unwrap_or_else(|e| panic!("{e}"));
+
+        let longitude = |subsec| {
+            let exif = exif_from(&[
+                ascii_field(Tag::DateTimeOriginal, "1970:01:01 00:00:05"),
+                ascii_field(Tag::OffsetTimeOriginal, "+00:00"),
+                ascii_field(Tag::SubSecTimeOriginal, subsec),
+            ]);
+            let timestamp = ImageGeotagger::get_image_timestamp(&exif, &CameraClock::default())
+                .unwrap_or_else(|e| panic!("{e}"));
+
+            flight
+                .get_fix_from_datetime(timestamp.time)
+                .unwrap_or_else(|e| panic!("{e}"))
+                .position
+                .longitude
+        };
+
+        let (first, second) = (longitude("10"), longitude("60"));
+        assert!((first - 0.051).abs() < 1e-9, "got {first}");
+        assert!((second - 0.056).abs() < 1e-9, "got {second}");
+    }
+
+    #[test]
+    fn camera_clock_ranks_above_the_gps_stamp() {
+        let exif = exif_from(&gps_stamped_fields(b"GPS"));
+        let timestamp = |camera_clock| {
+            ImageGeotagger::get_image_timestamp(&exif, &camera_clock)
+                .unwrap_or_else(|e| panic!("{e}"))
+                .time
+        };
+
+        assert_eq!(
+            timestamp(CameraClock::default()),
+            Utc.with_ymd_and_hms(2024, 1, 1, 10, 0, 0).unwrap()
+        );
+        assert_eq!(
+            timestamp(CameraClock {
+                tz: None,
+                utc_offset: FixedOffset::east_opt(3600),
+            }),
+            Utc.with_ymd_and_hms(2024, 1, 1, 11, 0, 0).unwrap()
+        );
+        assert_eq!(
+            timestamp(CameraClock {
+                tz: Some(chrono_tz::Asia::Tokyo),
+                utc_offset: FixedOffset::east_opt(3600),
+            }),
+            Utc.with_ymd_and_hms(2024, 1, 1, 3, 0, 0).unwrap()
+        );
+    }
+
+    #[test]
+    fn gps_stamps_written_by_a_previous_run_are_ignored() {
+        let exif = exif_from(&gps_stamped_fields(TrackFix::GPS_PROCESSING_METHOD));
+
+        assert!(ImageGeotagger::get_image_timestamp(&exif, &CameraClock::default()).is_err());
+    }
+
+    #[test]
+    fn existing_gps_fields_are_all_replaced() {
+        let old_fields = [
+            ascii_field(Tag::DateTimeOriginal, "2024:01:01 10:00:00"),
+            ascii_field(Tag::GPSSpeedRef, "K"),
+            Field {
+                tag: Tag::GPSSpeed,
+                ifd_num: In::PRIMARY,
+                value: Value::Rational(vec![Rational::from((12, 1))]),
+            },
+            ascii_field(Tag::GPSLatitudeRef, "S"),
+            ascii_field(Tag::GPSImgDirectionRef, "M"),
+            Field {
+                tag: Tag::GPSImgDirection,
+                ifd_num: In::PRIMARY,
+                value: Value::Rational(vec![Rational::from((90, 1))]),
+            },
+        ];
+        let exif = exif_from(&old_fields);
+
+        let position = GeoPosition {
+            timestamp: DateTime::<Utc>::UNIX_EPOCH,
+            latitude: 10.0,
+            longitude: 20.0,
+            altitude: 100,
+            speed: None,
+            heading: None,
+            vertical_speed: None,
+        };
+        let fix = TrackFix {
+            position,
+            before: position,
+            after: position,
+        };
+        let new_fields = fix.get_gps_exif().unwrap_or_else(|e| panic!("{e}"));
+        let flight =
+            FlightGeodata::new(String::new(), vec![position]).unwrap_or_else(|e| panic!("{e}"));
+        let geotagger = ImageGeotagger::new(ImageOutput::Directory(PathBuf::new()), flight);
+
+        let fields = geotagger.build_new_exif(&exif, &new_fields);
+
+        assert!(fields.iter().any(|f| f.tag == Tag::DateTimeOriginal));
+        assert!(!fields.iter().any(|f| f.tag == Tag::GPSSpeed));
+        // The camera's own direction isn't something this tool writes, so it survives.
+        assert!(fields.iter().any(|f| f.tag == Tag::GPSImgDirection));
+        assert!(fields.iter().any(|f| f.tag == Tag::GPSImgDirectionRef));
+        assert_eq!(
+            fields
+                .iter()
+                .filter(|f| f.tag.context() == Context::Gps)
+                .count(),
+            new_fields.len() + 2
+        );
     }
 }
@@ -0,0 +1,276 @@
+use std::{
+    collections::HashSet,
+    path::{Path, PathBuf},
+};
+
+use crate::{image_geotagger::ImageGeotagger, models::result::GTResult};
+
+/// Finds the images to geotag below a directory, filtered by glob patterns.
+///
+/// Patterns are matched case-insensitively against the `/`-separated path relative to the root
+/// directory, or against just the file name when the pattern has no `/`. `*` and `?` match
+/// within a path segment, while `**` matches across segments.
+pub struct ImageWalker {
+    root: PathBuf,
+    includes: Vec<String>,
+    excludes: Vec<String>,
+    max_depth: Option<usize>,
+    skip_dirs: Vec<PathBuf>,
+}
+
+impl ImageWalker {
+    pub fn new(root: PathBuf) -> Self {
+        Self {
+            root,
+            includes: Vec::new(),
+            excludes: Vec::new(),
+            max_depth: None,
+            skip_dirs: Vec::new(),
+        }
+    }
+
+    /// Only files matching at least one of these patterns are returned. No patterns means every
+    /// file is.
+    pub fn with_includes(mut self, includes: Vec<String>) -> Self {
+        self.includes = includes;
+        self
+    }
+
+    /// Files and directories matching any of these patterns are skipped.
+    pub fn with_excludes(mut self, excludes: Vec<String>) -> Self {
+        self.excludes = excludes;
+        self
+    }
+
+    /// How many levels of subdirectories to descend into. `Some(0)` only looks at the root
+    /// directory itself.
+    pub fn with_max_depth(mut self, max_depth: Option<usize>) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    /// Never descends into `dir`, such as when the output directory is inside the root.
+    pub fn with_skipped_dir(mut self, dir: PathBuf) -> Self {
+        self.skip_dirs.push(dir);
+        self
+    }
+
+    /// Returns the paths of all matching files relative to the root directory, in sorted order.
+    pub fn walk(&self) -> GTResult<Vec<PathBuf>> {
+        let skip_dirs = self
+            .skip_dirs
+            .iter()
+            .map(|dir| dir.canonicalize().unwrap_or_else(|_| dir.clone()))
+            .collect::<Vec<_>>();
+
+        // Symlinked directories are followed, so each one is only walked once to avoid loops.
+        let mut visited = HashSet::new();
+        visited.insert(self.root.canonicalize()?);
+
+        let mut files = Vec::new();
+        self.walk_dir(Path::new(""), 0, &skip_dirs, &mut visited, &mut files)?;
+        files.sort();
+
+        Ok(files)
+    }
+
+    fn walk_dir(
+        &self,
+        relative_dir: &Path,
+        depth: usize,
+        skip_dirs: &[PathBuf],
+        visited: &mut HashSet<PathBuf>,
+        files: &mut Vec<PathBuf>,
+    ) -> GTResult<()> {
+        let dir = self.root.join(relative_dir);
+        let mut entries = match std::fs::read_dir(&dir) {
+            Ok(entries) => entries,
+            // Only an unreadable root is fatal, as there's nothing to tag without it.
+            Err(e) if relative_dir.as_os_str().is_empty() => return Err(e.into()),
+            Err(e) => {
+                eprintln!("Can't read directory. Skipping {}: {e}", dir.display());
+                return Ok(());
+            }
+        }
+        .filter_map(|entry_res| {
+            entry_res
+                .inspect_err(|e| {
+                    eprintln!("Can't read entry in {}. Skipping it: {e}", dir.display())
+                })
+                .ok()
+        })
+        .collect::<Vec<_>>();
+        // Sorted so that a directory reachable through more than one symlink is always walked
+        // through the same one.
+        entries.sort_by_key(|entry| entry.file_name());
+
+        for entry in entries {
+            let path = entry.path();
+            let relative_path = relative_dir.join(entry.file_name());
+
+            if !path.exists() {
+                eprintln!(
+                    "Entry does not exist or is not file. Skipping {}",
+                    path.display()
+                );
+                continue;
+            }
+
+            if self.matches_any(&self.excludes, &relative_path) {
+                continue;
+            }
+
+            if path.is_dir() {
+                let canonical = path.canonicalize().unwrap_or_else(|_| path.clone());
+                let within_depth = self.max_depth.is_none_or(|max| depth < max);
+
+                if within_depth && !skip_dirs.contains(&canonical) && visited.insert(canonical) {
+                    self.walk_dir(&relative_path, depth + 1, skip_dirs, visited, files)?;
+                }
+                continue;
+            }
+
+            // Left behind by a run which was interrupted while tagging in place.
+            if path
+                .as_os_str()
+                .to_string_lossy()
+                .ends_with(ImageGeotagger::TEMP_SUFFIX)
+            {
+                continue;
+            }
+
+            if self.includes.is_empty() || self.matches_any(&self.includes, &relative_path) {
+                files.push(relative_path);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn matches_any(&self, patterns: &[String], relative_path: &Path) -> bool {
+        let path = relative_path
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/")
+            .to_lowercase();
+        let file_name = relative_path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+
+        patterns.iter().any(|pattern| {
+            let pattern = pattern.to_lowercase().chars().collect::<Vec<_>>();
+            let text = if pattern.contains(&'/') {
+                &path
+            } else {
+                &file_name
+            };
+
+            glob_match(&pattern, &text.chars().collect::<Vec<_>>())
+        })
+    }
+}
+
+fn glob_match(pattern: &[char], text: &[char]) -> bool {
+    match pattern {
+        [] => text.is_empty(),
+        ['*', '*', rest @ ..] => {
+            // `**/` may also match no directories at all.
+            let without_slash = rest.strip_prefix(&['/']).unwrap_or(rest);
+
+            glob_match(without_slash, text)
+                || (0..=text.len()).any(|i| glob_match(rest, &text[i..]))
+        }
+        ['*', rest @ ..] => (0..=text.len())
+            .take_while(|&i| i == 0 || text[i - 1] != '/')
+            .any(|i| glob_match(rest, &text[i..])),
+        ['?', rest @ ..] => text.first().is_some_and(|c| *c != '/') && glob_match(rest, &text[1..]),
+        [c, rest @ ..] => text.first() == Some(c) && glob_match(rest, &text[1..]),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::ImageWalker;
+
+    fn scratch_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("image-walker-{name}-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(dir.join("sub")).unwrap();
+        std::fs::write(dir.join("a.jpg"), b"").unwrap();
+        std::fs::write(dir.join("sub/b.jpg"), b"").unwrap();
+
+        dir
+    }
+
+    #[test]
+    fn walks_filtered_files_in_sorted_order() {
+        let dir = scratch_dir("filter");
+        std::fs::write(dir.join("sub/notes.txt"), b"").unwrap();
+
+        let files = ImageWalker::new(dir.clone())
+            .with_includes(vec!["*.jpg".to_string()])
+            .walk()
+            .unwrap_or_else(|e| panic!("{e}"));
+
+        assert_eq!(files, [PathBuf::from("a.jpg"), PathBuf::from("sub/b.jpg")]);
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn leftover_temp_files_are_skipped() {
+        let dir = scratch_dir("temp");
+        std::fs::write(dir.join("sub/b.jpg.geotagging"), b"").unwrap();
+
+        let files = ImageWalker::new(dir.clone())
+            .walk()
+            .unwrap_or_else(|e| panic!("{e}"));
+
+        assert_eq!(files, [PathBuf::from("a.jpg"), PathBuf::from("sub/b.jpg")]);
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn symlinked_directories_are_walked_once() {
+        let dir = scratch_dir("symlink");
+        std::os::unix::fs::symlink(&dir, dir.join("sub/loop")).unwrap();
+        std::os::unix::fs::symlink(dir.join("sub"), dir.join("alias")).unwrap();
+
+        let files = ImageWalker::new(dir.clone())
+            .walk()
+            .unwrap_or_else(|e| panic!("{e}"));
+
+        // `alias` sorts first, so `sub` is reached through it.
+        assert_eq!(
+            files,
+            [PathBuf::from("a.jpg"), PathBuf::from("alias/b.jpg")]
+        );
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn unreadable_directories_are_skipped() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = scratch_dir("unreadable");
+        let locked = dir.join("sub");
+        std::fs::set_permissions(&locked, std::fs::Permissions::from_mode(0o000)).unwrap();
+
+        let files = ImageWalker::new(dir.clone()).walk();
+        std::fs::set_permissions(&locked, std::fs::Permissions::from_mode(0o755)).unwrap();
+        let files = files.unwrap_or_else(|e| panic!("{e}"));
+
+        // Running as root can still read it.
+        assert!(files.contains(&PathBuf::from("a.jpg")));
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -1,62 +1,170 @@
 mod cli;
 mod data_providers;
+mod dry_run;
+mod flight_cache;
 mod image_geotagger;
+mod image_walker;
 mod models;
 mod parsers;
+mod run_summary;
+mod worker_pool;
 
-use std::process::exit;
+use std::{fs::File, io::BufWriter, process::exit};
 
+use chrono::TimeDelta;
 use clap::Parser;
-use cli::{Cli, TagArgs};
+use cli::{CacheCommand, Cli, TagArgs};
+use dry_run::DryRunReport;
+use flight_cache::FlightCache;
 use image_geotagger::ImageGeotagger;
 use models::result::GTResult;
+use parsers::geojson_parser::GeoJsonParser;
+use run_summary::{ReportFormat, RunSummary};
 
 fn main() {
-    let Cli::Tag(tag) = Cli::parse();
+    let tag = match Cli::parse() {
+        Cli::Tag(tag) => *tag,
+        Cli::Cache(command) => {
+            if let Err(e) = run_cache(command) {
+                eprintln!("Error managing flight data cache. Error: {e}");
 
-    println!("Geotagger started!");
+                exit(1)
+            }
 
-    if let Err(e) = run(tag) {
-        println!("Fatal error running geotagger. Exiting. Error: {e}");
+            return;
+        }
+    };
 
-        exit(1)
-    }
-}
+    eprintln!("Geotagger started!");
 
-fn run(args: TagArgs) -> GTResult<()> {
-    let provider = args.try_get_provider()?;
-    let flight_data = provider.load_data()?;
+    match run(tag) {
+        Ok(code) => exit(code),
+        Err(e) => {
+            eprintln!("Fatal error running geotagger. Exiting. Error: {e}");
 
-    println!("Obtained data: {flight_data}");
+            exit(1)
+        }
+    }
+}
 
-    let output_dir = args.images_dir.join("geotagged");
-    let mapper = ImageGeotagger::new(output_dir, flight_data);
+fn run_cache(command: CacheCommand) -> GTResult<()> {
+    let cache = FlightCache::open_default()?;
 
-    for entry_res in std::fs::read_dir(args.images_dir)? {
-        let entry = entry_res?;
-        let path = entry.path();
+    match command {
+        CacheCommand::List => {
+            let entries = cache.entries()?;
 
-        if !path.exists() {
             println!(
-                "Entry does not exist or is not file. Skipping {}",
-                path.display()
+                "{} cached flights in '{}'.",
+                entries.len(),
+                cache.dir().display()
             );
-            continue;
-        }
-
-        if path.is_dir() {
-            continue;
+            for entry in entries {
+                println!("  {entry}");
+            }
         }
+        CacheCommand::Clear => {
+            let removed = cache.clear()?;
 
-        if let Err(e) = mapper.apply_gps_data(&path) {
             println!(
-                "Error processing image {}: {e}",
-                path.file_name().unwrap().to_str().unwrap()
+                "Removed {removed} cached flights from '{}'.",
+                cache.dir().display()
             );
         }
     }
 
+    Ok(())
+}
+
+/// Geotags the images, returning the exit code for the run.
+fn run(args: TagArgs) -> GTResult<i32> {
+    let report_format = args
+        .report
+        .as_deref()
+        .map(ReportFormat::try_from_path)
+        .transpose()?;
+
+    let clock_correction = args.clock_correction()?;
+    let provider = args.try_get_provider(clock_correction.known_offset())?;
+    let flight_data = provider
+        .load_data()?
+        .with_interpolation(args.interpolation)
+        .with_extrapolation(
+            args.extrapolate,
+            TimeDelta::seconds(args.dead_reckoning_limit.into()),
+        );
+
+    eprintln!("Obtained data: {flight_data}");
+
+    let time_offset = clock_correction.resolve(&flight_data)?;
+
+    if let Some(path) = args.export_geojson.as_ref().filter(|_| !args.dry_run) {
+        let geojson = GeoJsonParser {}.to_geojson(&flight_data);
+        let writer = BufWriter::new(File::create(path)?);
+        serde_json::to_writer_pretty(writer, &geojson)?;
+
+        eprintln!("Exported flight track to '{}'.", path.display());
+    }
+
+    let images = args.find_images()?;
+    let mapper = ImageGeotagger::new(args.image_output(), flight_data)
+        .with_time_offset(time_offset)
+        .with_camera_clock(args.camera_clock())
+        .with_max_gap(
+            args.max_gap.map(|secs| TimeDelta::seconds(secs.into())),
+            args.on_gap,
+        );
+
+    if args.dry_run {
+        let mut report = DryRunReport::default();
+
+        worker_pool::process_in_order(
+            &images,
+            args.jobs(),
+            |relative_path| mapper.locate_image(&args.images_dir.join(relative_path)),
+            |relative_path, location| report.add(relative_path.clone(), location),
+        );
+
+        report.print(args.dry_run_format);
+
+        return Ok(0);
+    }
+
+    let jobs = args.jobs();
+    println!("Geotagging {} images with {jobs} workers.", images.len());
+
+    let mut summary = RunSummary::default();
+    worker_pool::process_in_order(
+        &images,
+        jobs,
+        |relative_path| {
+            let mut log = Vec::new();
+            let path = args.images_dir.join(relative_path);
+            let result = mapper.apply_gps_data(&path, relative_path, &mut log);
+
+            (log, result)
+        },
+        |relative_path, (log, result)| {
+            println!("\nProcessing image {}", relative_path.display());
+            for line in log {
+                println!("{line}");
+            }
+
+            if let Err(ref e) = result {
+                println!("Error processing image {}: {e}", relative_path.display());
+            }
+
+            summary.add(relative_path.clone(), &result);
+        },
+    );
+
+    summary.print();
+
+    if let (Some(path), Some(format)) = (&args.report, report_format) {
+        summary.write_report(path, format)?;
+    }
+
     println!("All done.");
 
-    Ok(())
+    Ok(summary.exit_code())
 }
@@ -1,3 +1,6 @@
+pub mod camera_clock;
 pub mod coord;
+pub mod flight_candidate;
 pub mod flight_geodata;
+pub mod interpolator;
 pub mod result;
@@ -0,0 +1,42 @@
+use std::fmt::Display;
+
+use chrono::{DateTime, FixedOffset, Utc};
+use chrono_tz::Tz;
+
+/// What the camera's clock was set to, for images which don't record their own UTC offset.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct CameraClock {
+    pub tz: Option<Tz>,
+    pub utc_offset: Option<FixedOffset>,
+}
+
+/// Where the UTC offset of an image timestamp came from.
+#[derive(Clone, Copy, Debug)]
+pub enum TimestampSource {
+    OffsetTimeOriginal,
+    OffsetTime,
+    OffsetTimeDigitized,
+    GpsTimestamp,
+    CameraTz(Tz),
+    CameraUtcOffset(FixedOffset),
+}
+
+impl Display for TimestampSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::OffsetTimeOriginal => write!(f, "OffsetTimeOriginal"),
+            Self::OffsetTime => write!(f, "OffsetTime"),
+            Self::OffsetTimeDigitized => write!(f, "OffsetTimeDigitized"),
+            Self::GpsTimestamp => write!(f, "GPS timestamp"),
+            Self::CameraTz(tz) => write!(f, "camera tz {tz}"),
+            Self::CameraUtcOffset(offset) => write!(f, "camera offset {offset}"),
+        }
+    }
+}
+
+/// The instant an image was captured, and how its UTC offset was worked out.
+#[derive(Clone, Copy, Debug)]
+pub struct ImageTimestamp {
+    pub time: DateTime<Utc>,
+    pub source: TimestampSource,
+}
@@ -6,7 +6,21 @@ use super::result::{GTError, GTResult};
 pub struct Converter {}
 
 impl Converter {
+    const METRES_PER_FOOT: f64 = 0.3048;
+    const METRES_PER_SECOND_PER_KNOT: f64 = 1852.0 / 3600.0;
+
+    pub fn feet_to_metres(feet: f64) -> f64 {
+        feet * Self::METRES_PER_FOOT
+    }
+
+    pub fn knots_to_metres_per_second(knots: f64) -> f64 {
+        knots * Self::METRES_PER_SECOND_PER_KNOT
+    }
+
+    /// Converts a coordinate to unsigned degrees, minutes and seconds. The hemisphere is left to
+    /// the caller, as EXIF gives it in a separate reference tag.
     pub fn try_coord_to_dms(coord: f64) -> Option<(u32, u32, Rational32)> {
+        let coord = coord.abs();
         let degrees = coord.floor();
         let part = (coord - degrees) * 60.0;
         let minutes = part.floor();
@@ -28,3 +42,168 @@ impl Converter {
         Ok(Value::Rational(vec![degrees, minutes, seconds]))
     }
 }
+
+/// Great-circle paths on a spherical model of the earth, which is within a fraction of a percent
+/// of WGS-84 over the distances between track points.
+pub struct GreatCircle {}
+
+impl GreatCircle {
+    pub const EARTH_RADIUS_METRES: f64 = 6_371_008.8;
+
+    fn to_unit_vector(latitude: f64, longitude: f64) -> [f64; 3] {
+        let (lat, lon) = (latitude.to_radians(), longitude.to_radians());
+
+        [lat.cos() * lon.cos(), lat.cos() * lon.sin(), lat.sin()]
+    }
+
+    fn from_vector([x, y, z]: [f64; 3]) -> (f64, f64) {
+        let latitude = z.atan2(x.hypot(y)).to_degrees();
+        let longitude = y.atan2(x).to_degrees();
+
+        (latitude, longitude)
+    }
+
+    /// The length in metres of the shortest path between two `(latitude, longitude)` positions.
+    pub fn distance(from: (f64, f64), to: (f64, f64)) -> f64 {
+        let a = Self::to_unit_vector(from.0, from.1);
+        let b = Self::to_unit_vector(to.0, to.1);
+
+        let chord = (a[0] - b[0]).hypot(a[1] - b[1]).hypot(a[2] - b[2]);
+
+        Self::EARTH_RADIUS_METRES * 2.0 * (chord / 2.0).min(1.0).asin()
+    }
+
+    /// The `(latitude, longitude)` reached by travelling `distance` metres from `from` with an
+    /// initial bearing of `bearing` degrees. A negative distance travels backwards.
+    pub fn destination(from: (f64, f64), bearing: f64, distance: f64) -> (f64, f64) {
+        let (lat, lon) = (from.0.to_radians(), from.1.to_radians());
+        let bearing = bearing.to_radians();
+        let angle = distance / Self::EARTH_RADIUS_METRES;
+
+        let dest_lat = (lat.sin() * angle.cos() + lat.cos() * angle.sin() * bearing.cos()).asin();
+        let dest_lon = lon
+            + (bearing.sin() * angle.sin() * lat.cos())
+                .atan2(angle.cos() - lat.sin() * dest_lat.sin());
+
+        Self::from_vector(Self::to_unit_vector(
+            dest_lat.to_degrees(),
+            dest_lon.to_degrees(),
+        ))
+    }
+
+    /// Returns the point `factor` of the way along the shortest path from the first position to
+    /// the second, as `(latitude, longitude)` with the longitude in -180..=180. Unlike
+    /// interpolating the coordinates themselves, this follows the antimeridian and poles
+    /// correctly.
+    pub fn interpolate(from: (f64, f64), to: (f64, f64), factor: f64) -> (f64, f64) {
+        let a = Self::to_unit_vector(from.0, from.1);
+        let b = Self::to_unit_vector(to.0, to.1);
+
+        let dot = a[0] * b[0] + a[1] * b[1] + a[2] * b[2];
+        let cross = [
+            a[1] * b[2] - a[2] * b[1],
+            a[2] * b[0] - a[0] * b[2],
+            a[0] * b[1] - a[1] * b[0],
+        ];
+        let angle = (cross[0].hypot(cross[1]).hypot(cross[2])).atan2(dot);
+
+        // Close enough together that the path is a straight line, and sin(angle) would be ~0.
+        let (weight_a, weight_b) = if angle.abs() < 1e-12 {
+            (1.0 - factor, factor)
+        } else {
+            (
+                ((1.0 - factor) * angle).sin() / angle.sin(),
+                (factor * angle).sin() / angle.sin(),
+            )
+        };
+
+        Self::from_vector([
+            weight_a * a[0] + weight_b * b[0],
+            weight_a * a[1] + weight_b * b[1],
+            weight_a * a[2] + weight_b * b[2],
+        ])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::GreatCircle;
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!(
+            (actual - expected).abs() < 1e-6,
+            "expected {expected}, got {actual}"
+        );
+    }
+
+    #[test]
+    fn interpolates_along_the_equator() {
+        let (lat, lon) = GreatCircle::interpolate((0.0, 10.0), (0.0, 20.0), 0.25);
+
+        assert_close(lat, 0.0);
+        assert_close(lon, 12.5);
+    }
+
+    #[test]
+    fn crosses_the_antimeridian_the_short_way() {
+        let (lat, lon) = GreatCircle::interpolate((0.0, 179.9), (0.0, -179.9), 0.5);
+
+        assert_close(lat, 0.0);
+        assert_close(lon.abs(), 180.0);
+
+        let (_, lon) = GreatCircle::interpolate((0.0, 179.9), (0.0, -179.9), 0.75);
+        assert_close(lon, -179.95);
+    }
+
+    #[test]
+    fn crosses_the_pole() {
+        let (lat, _) = GreatCircle::interpolate((80.0, 0.0), (80.0, 180.0), 0.5);
+        assert_close(lat, 90.0);
+
+        let (lat, lon) = GreatCircle::interpolate((80.0, 0.0), (80.0, 180.0), 0.75);
+        assert_close(lat, 85.0);
+        assert_close(lon, 180.0);
+    }
+
+    #[test]
+    fn bows_towards_the_pole_on_long_high_latitude_legs() {
+        // Half way between Helsinki and Anchorage on the great circle is far north of the
+        // average latitude.
+        let (lat, _) = GreatCircle::interpolate((60.17, 24.94), (61.22, -149.9), 0.5);
+
+        assert!(lat > 80.0, "expected a polar route, got latitude {lat}");
+    }
+
+    #[test]
+    fn measures_distance_along_the_surface() {
+        // One degree along the equator, and a quarter of the way round the earth.
+        let degree = GreatCircle::EARTH_RADIUS_METRES * std::f64::consts::PI / 180.0;
+
+        assert!((GreatCircle::distance((0.0, 0.0), (0.0, 1.0)) - degree).abs() < 1e-3);
+        assert!((GreatCircle::distance((0.0, 0.0), (90.0, 0.0)) - 90.0 * degree).abs() < 1e-3);
+    }
+
+    #[test]
+    fn travels_to_a_destination_and_back() {
+        let degree = GreatCircle::EARTH_RADIUS_METRES * std::f64::consts::PI / 180.0;
+
+        let (lat, lon) = GreatCircle::destination((0.0, 179.5), 90.0, degree);
+        assert_close(lat, 0.0);
+        assert_close(lon, -179.5);
+
+        let (lat, lon) = GreatCircle::destination((10.0, 20.0), 0.0, -degree);
+        assert_close(lat, 9.0);
+        assert_close(lon, 20.0);
+    }
+
+    #[test]
+    fn endpoints_are_unchanged() {
+        let (lat, lon) = GreatCircle::interpolate((35.85, 14.48), (51.47, -0.45), 0.0);
+        assert_close(lat, 35.85);
+        assert_close(lon, 14.48);
+
+        let (lat, lon) = GreatCircle::interpolate((35.85, 14.48), (51.47, -0.45), 1.0);
+        assert_close(lat, 51.47);
+        assert_close(lon, -0.45);
+    }
+}
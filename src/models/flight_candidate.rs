@@ -0,0 +1,174 @@
+use std::fmt::Display;
+
+use chrono::{DateTime, Duration, Utc};
+
+/// One operated instance of a flight code, as listed by a flight lookup.
+#[derive(Clone, Debug)]
+pub struct FlightCandidate {
+    /// Provider-specific identifier of this instance (the FlightRadar24 flight hex).
+    pub flight_id: String,
+    pub flight_code: String,
+    pub scheduled_departure: Option<DateTime<Utc>>,
+    pub scheduled_arrival: Option<DateTime<Utc>>,
+    pub actual_departure: Option<DateTime<Utc>>,
+    pub actual_arrival: Option<DateTime<Utc>>,
+    pub origin: Option<String>,
+    pub destination: Option<String>,
+}
+
+impl FlightCandidate {
+    /// Photos are often taken at the gate before departure or after arrival, so the time window
+    /// is widened by this much at both ends.
+    const WINDOW_MARGIN_MINUTES: i64 = 60;
+
+    pub fn departure(&self) -> Option<DateTime<Utc>> {
+        self.actual_departure.or(self.scheduled_departure)
+    }
+
+    pub fn arrival(&self) -> Option<DateTime<Utc>> {
+        self.actual_arrival.or(self.scheduled_arrival)
+    }
+
+    /// The span of time in which photos taken on this flight are expected, preferring actual
+    /// times over scheduled ones.
+    pub fn time_window(&self) -> Option<(DateTime<Utc>, DateTime<Utc>)> {
+        let margin = Duration::minutes(Self::WINDOW_MARGIN_MINUTES);
+
+        Some((self.departure()? - margin, self.arrival()? + margin))
+    }
+
+    pub fn covers(&self, timestamp: DateTime<Utc>) -> bool {
+        self.time_window()
+            .is_some_and(|(start, end)| start <= timestamp && timestamp <= end)
+    }
+
+    /// Picks the candidate whose time window covers the most of the given photo timestamps. Ties
+    /// go to the candidate whose window is centred closest to the median photo timestamp.
+    pub fn pick_for_timestamps<'a>(
+        candidates: &'a [FlightCandidate],
+        timestamps: &[DateTime<Utc>],
+    ) -> Option<&'a FlightCandidate> {
+        let mut sorted_timestamps = timestamps.to_vec();
+        sorted_timestamps.sort();
+        let median = *sorted_timestamps.get(sorted_timestamps.len() / 2)?;
+
+        candidates
+            .iter()
+            .filter_map(|candidate| {
+                let covered = timestamps.iter().filter(|t| candidate.covers(**t)).count();
+                let (start, end) = candidate.time_window()?;
+                let centre = start + (end - start) / 2;
+
+                (covered > 0).then_some((candidate, covered, (centre - median).abs()))
+            })
+            .max_by(|(_, a_covered, a_distance), (_, b_covered, b_distance)| {
+                a_covered
+                    .cmp(b_covered)
+                    .then_with(|| b_distance.cmp(a_distance))
+            })
+            .map(|(candidate, _, _)| candidate)
+    }
+}
+
+impl Display for FlightCandidate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let format_time = |time: Option<DateTime<Utc>>| {
+            time.map_or("--".to_string(), |t| {
+                t.format("%Y-%m-%d %H:%M UTC").to_string()
+            })
+        };
+
+        write!(
+            f,
+            "{} [{}] {} -> {}, departs {}, arrives {}",
+            self.flight_code,
+            self.flight_id,
+            self.origin.as_deref().unwrap_or("???"),
+            self.destination.as_deref().unwrap_or("???"),
+            format_time(self.departure()),
+            format_time(self.arrival()),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{DateTime, TimeDelta, Utc};
+
+    use super::FlightCandidate;
+
+    fn time(minutes: i64) -> DateTime<Utc> {
+        DateTime::<Utc>::UNIX_EPOCH + TimeDelta::minutes(minutes)
+    }
+
+    fn candidate(flight_id: &str, departure: i64, arrival: i64) -> FlightCandidate {
+        FlightCandidate {
+            flight_id: flight_id.to_string(),
+            flight_code: "BA123".to_string(),
+            scheduled_departure: Some(time(departure)),
+            scheduled_arrival: Some(time(arrival)),
+            actual_departure: None,
+            actual_arrival: None,
+            origin: None,
+            destination: None,
+        }
+    }
+
+    fn pick(candidates: &[FlightCandidate], photo_minutes: &[i64]) -> Option<String> {
+        let timestamps = photo_minutes.iter().map(|&m| time(m)).collect::<Vec<_>>();
+
+        FlightCandidate::pick_for_timestamps(candidates, &timestamps).map(|c| c.flight_id.clone())
+    }
+
+    #[test]
+    fn picks_the_flight_covering_the_photos() {
+        // Morning and evening rotations of the same flight code.
+        let candidates = [
+            candidate("morning", 480, 660),
+            candidate("evening", 1080, 1260),
+        ];
+
+        assert_eq!(
+            pick(&candidates, &[1100, 1150, 1200]).as_deref(),
+            Some("evening")
+        );
+        assert_eq!(pick(&candidates, &[500, 550]).as_deref(), Some("morning"));
+    }
+
+    #[test]
+    fn picks_the_flight_covering_the_most_photos() {
+        let candidates = [
+            candidate("morning", 480, 660),
+            candidate("evening", 1080, 1260),
+        ];
+
+        // A stray photo from before the evening flight doesn't outweigh the rest.
+        assert_eq!(
+            pick(&candidates, &[500, 1100, 1150]).as_deref(),
+            Some("evening")
+        );
+    }
+
+    #[test]
+    fn no_flight_is_picked_without_coverage() {
+        let candidates = [
+            candidate("morning", 480, 660),
+            candidate("evening", 1080, 1260),
+        ];
+
+        assert_eq!(pick(&candidates, &[800, 900]), None);
+        assert_eq!(pick(&candidates, &[]), None);
+    }
+
+    #[test]
+    fn ties_go_to_the_window_centred_nearest_the_median_photo() {
+        // Overlapping windows, both of which cover every photo.
+        let candidates = [candidate("early", 480, 600), candidate("late", 560, 680)];
+
+        assert_eq!(pick(&candidates, &[600, 620, 640]).as_deref(), Some("late"));
+        assert_eq!(
+            pick(&candidates, &[520, 540, 560]).as_deref(),
+            Some("early")
+        );
+    }
+}
@@ -1,10 +1,11 @@
 use std::fmt::Display;
 
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, TimeDelta, Timelike, Utc};
 use exif::{Field, In, Rational, Tag, Value};
 
 use super::{
-    coord::Converter,
+    coord::{Converter, GreatCircle},
+    interpolator::{Extrapolation, Interpolation},
     result::{GTError, GTResult},
 };
 
@@ -14,6 +15,12 @@ pub struct GeoPosition {
     pub latitude: f64,
     pub longitude: f64,
     pub altitude: i64,
+    /// Ground speed in knots.
+    pub speed: Option<f64>,
+    /// Direction of travel over the ground, in degrees clockwise from true north.
+    pub heading: Option<f64>,
+    /// Rate of climb in feet per minute.
+    pub vertical_speed: Option<f64>,
 }
 
 impl Display for GeoPosition {
@@ -26,82 +33,95 @@ impl Display for GeoPosition {
     }
 }
 
-#[derive(Clone, Debug)]
-pub struct FlightGeodata {
-    pub flight_code: String,
-    positions: Vec<GeoPosition>,
+/// A position on the track, along with the recorded track points it was interpolated between, or
+/// extrapolated from for positions beyond the ends of the track.
+#[derive(Clone, Copy, Debug)]
+pub struct TrackFix {
+    pub position: GeoPosition,
+    pub before: GeoPosition,
+    pub after: GeoPosition,
 }
 
-impl FlightGeodata {
-    pub fn new(flight_code: String, mut positions: Vec<GeoPosition>) -> Self {
-        positions.sort_by_key(|p| p.timestamp);
-
-        Self {
-            flight_code,
-            positions,
-        }
-    }
-
-    fn binary_search_positions(&self, timestamp: DateTime<Utc>) -> GTResult<usize> {
-        if timestamp < self.positions[0].timestamp {
-            return Err(GTError::MissingData(format!(
-                "Image timestamp '{timestamp}' is older than flight position data. Oldest timestamp: {}",
-                self.positions[0].timestamp
-            )));
-        }
+impl TrackFix {
+    const GPS_VERSION_ID: [u8; 4] = [2, 3, 0, 0];
+    const GPS_MAP_DATUM: &'static [u8] = b"WGS-84";
+    pub const GPS_PROCESSING_METHOD: &'static [u8] = b"FLIGHT TRACK";
 
-        if timestamp > self.positions[self.positions.len() - 1].timestamp {
-            return Err(GTError::MissingData(format!(
-                "Image timestamp '{timestamp}' is newer than flight position data. Newest timestamp: {}",
-                self.positions[self.positions.len() - 1].timestamp
-            )));
-        }
+    /// Accuracy of the recorded track points themselves, as for a typical ADS-B position report.
+    const BASE_ERROR_METRES: f64 = 10.0;
+    /// How far off the interpolated path the aircraft may have strayed, as a fraction of the
+    /// distance flown since (or until) the nearest track point. Allows for gentle turns.
+    const DRIFT_FACTOR: f64 = 0.2;
 
-        match self
-            .positions
-            .binary_search_by_key(&timestamp, |p| p.timestamp)
-        {
-            Ok(i) => Ok(i),
-            Err(i) => Ok(i),
-        }
+    /// The time between the two track points around the position.
+    pub fn gap(&self) -> TimeDelta {
+        self.after.timestamp - self.before.timestamp
     }
 
-    pub fn get_position_from_datetime(&self, timestamp: DateTime<Utc>) -> GTResult<GeoPosition> {
-        let closest_position = self.binary_search_positions(timestamp)?;
+    /// A rough estimate of the horizontal error of the position in metres, which grows with the
+    /// time to the nearest recorded track point and with the ground speed.
+    pub fn horizontal_error(&self) -> f64 {
+        // Extrapolated positions lie outside the two track points, so use absolute times.
+        let since_before = (self.position.timestamp - self.before.timestamp).abs();
+        let until_after = (self.after.timestamp - self.position.timestamp).abs();
+        let nearest_seconds = since_before.min(until_after).num_milliseconds() as f64 / 1000.0;
 
-        println!("Closest position index: {closest_position}");
+        let speed = match self.position.speed {
+            Some(knots) => Converter::knots_to_metres_per_second(knots),
+            None => {
+                let gap_seconds = self.gap().num_milliseconds() as f64 / 1000.0;
+                if gap_seconds > 0.0 {
+                    GreatCircle::distance(
+                        (self.before.latitude, self.before.longitude),
+                        (self.after.latitude, self.after.longitude),
+                    ) / gap_seconds
+                } else {
+                    0.0
+                }
+            }
+        };
 
-        if self.positions[closest_position].timestamp == timestamp {
-            return Ok(self.positions[closest_position].clone());
-        }
+        Self::BASE_ERROR_METRES + Self::DRIFT_FACTOR * speed * nearest_seconds
+    }
 
-        let lower_position = &self.positions[closest_position - 1];
-        let higher_position = &self.positions[closest_position];
+    fn to_hundredths(value: f64) -> GTResult<Rational> {
+        let hundredths = u32::try_from((value.abs() * 100.0).round() as i64)?;
 
-        let linear_factor = (timestamp.timestamp() - lower_position.timestamp.timestamp()) as f64
-            / (higher_position.timestamp.timestamp() - lower_position.timestamp.timestamp()) as f64;
+        Ok(Rational::from((hundredths, 100)))
+    }
 
-        let interpolated_position = GeoPosition {
-            timestamp,
-            latitude: lower_position.latitude
-                + linear_factor * (higher_position.latitude - lower_position.latitude),
-            longitude: lower_position.longitude
-                + linear_factor * (higher_position.longitude - lower_position.longitude),
-            altitude: (lower_position.altitude as f64
-                + linear_factor * (higher_position.altitude - lower_position.altitude) as f64)
-                as i64,
-        };
-
-        println!(
-            "Lower position: {:?}\nInterpolated position: {:?}\nHigher position: {:?}",
-            lower_position, interpolated_position, higher_position
-        );
+    /// GPSTrack must be in `[0, 360)`, so the heading is normalised after rounding, which could
+    /// otherwise turn 359.999 into 360.
+    fn to_track_direction(heading: f64) -> GTResult<Rational> {
+        let hundredths = (heading.rem_euclid(360.0) * 100.0).round() as i64 % 36_000;
 
-        Ok(interpolated_position)
+        Ok(Rational::from((u32::try_from(hundredths)?, 100)))
     }
 
-    pub fn get_gps_exif_from_datetime(&self, timestamp: DateTime<Utc>) -> GTResult<[Field; 6]> {
-        let position = self.get_position_from_datetime(timestamp)?;
+    /// Every GPS tag `get_gps_exif` may write. Existing copies of them are stale once an image is
+    /// re-tagged, even those this fix has no value for, such as GPSSpeed without a known speed.
+    pub const GPS_TAGS: [Tag; 16] = [
+        Tag::GPSVersionID,
+        Tag::GPSLatitudeRef,
+        Tag::GPSLatitude,
+        Tag::GPSLongitudeRef,
+        Tag::GPSLongitude,
+        Tag::GPSAltitudeRef,
+        Tag::GPSAltitude,
+        Tag::GPSTimeStamp,
+        Tag::GPSDateStamp,
+        Tag::GPSMapDatum,
+        Tag::GPSProcessingMethod,
+        Tag::GPSSpeedRef,
+        Tag::GPSSpeed,
+        Tag::GPSTrackRef,
+        Tag::GPSTrack,
+        Tag::GPSHPositioningError,
+    ];
+
+    /// Builds the GPS EXIF fields describing this fix.
+    pub fn get_gps_exif(&self) -> GTResult<Vec<Field>> {
+        let position = self.position;
 
         let lat_ref = if position.latitude >= 0.0 { b"N" } else { b"S" };
         let lat_ref = Field {
@@ -149,17 +169,549 @@ impl FlightGeodata {
             value: Value::Rational(vec![alt]),
         };
 
-        Ok([lat_ref, lat, lon_ref, lon, alt_ref, alt])
+        let version = Field {
+            tag: Tag::GPSVersionID,
+            ifd_num: In::PRIMARY,
+            value: Value::Byte(Self::GPS_VERSION_ID.to_vec()),
+        };
+
+        let utc_time = position.timestamp.time();
+        let time = Field {
+            tag: Tag::GPSTimeStamp,
+            ifd_num: In::PRIMARY,
+            value: Value::Rational(vec![
+                Rational::from((utc_time.hour(), 1)),
+                Rational::from((utc_time.minute(), 1)),
+                Rational::from((
+                    utc_time.second() * 1000 + utc_time.nanosecond() / 1_000_000,
+                    1000,
+                )),
+            ]),
+        };
+
+        let date = Field {
+            tag: Tag::GPSDateStamp,
+            ifd_num: In::PRIMARY,
+            value: Value::Ascii(vec![position
+                .timestamp
+                .format("%Y:%m:%d")
+                .to_string()
+                .into_bytes()]),
+        };
+
+        let datum = Field {
+            tag: Tag::GPSMapDatum,
+            ifd_num: In::PRIMARY,
+            value: Value::Ascii(vec![Self::GPS_MAP_DATUM.to_vec()]),
+        };
+
+        let processing_method = Field {
+            tag: Tag::GPSProcessingMethod,
+            ifd_num: In::PRIMARY,
+            value: Value::Undefined(
+                [b"ASCII\0\0\0".as_slice(), Self::GPS_PROCESSING_METHOD].concat(),
+                0,
+            ),
+        };
+
+        let mut fields = vec![
+            version,
+            lat_ref,
+            lat,
+            lon_ref,
+            lon,
+            alt_ref,
+            alt,
+            time,
+            date,
+            datum,
+            processing_method,
+        ];
+
+        if let Some(speed) = position.speed {
+            fields.push(Field {
+                tag: Tag::GPSSpeedRef,
+                ifd_num: In::PRIMARY,
+                value: Value::Ascii(vec![b"N".to_vec()]),
+            });
+            fields.push(Field {
+                tag: Tag::GPSSpeed,
+                ifd_num: In::PRIMARY,
+                value: Value::Rational(vec![Self::to_hundredths(speed)?]),
+            });
+        }
+
+        // GPSImgDirection is left alone, since which way the camera faced relative to the
+        // aircraft isn't known.
+        if let Some(heading) = position.heading {
+            fields.push(Field {
+                tag: Tag::GPSTrackRef,
+                ifd_num: In::PRIMARY,
+                value: Value::Ascii(vec![b"T".to_vec()]),
+            });
+            fields.push(Field {
+                tag: Tag::GPSTrack,
+                ifd_num: In::PRIMARY,
+                value: Value::Rational(vec![Self::to_track_direction(heading)?]),
+            });
+        }
+
+        fields.push(Field {
+            tag: Tag::GPSHPositioningError,
+            ifd_num: In::PRIMARY,
+            value: Value::Rational(vec![Self::to_hundredths(self.horizontal_error())?]),
+        });
+
+        Ok(fields)
+    }
+}
+
+/// An airport at one end of a flight.
+#[derive(Clone, Debug)]
+pub struct Airport {
+    pub code: String,
+    pub latitude: f64,
+    pub longitude: f64,
+    /// Elevation in metres.
+    pub altitude: i64,
+}
+
+#[derive(Clone, Debug)]
+pub struct FlightGeodata {
+    pub flight_code: String,
+    positions: Vec<GeoPosition>,
+    pub origin: Option<Airport>,
+    pub destination: Option<Airport>,
+    interpolation: Interpolation,
+    extrapolation: Option<Extrapolation>,
+    dead_reckoning_limit: TimeDelta,
+}
+
+impl FlightGeodata {
+    /// Fails if `positions` is empty, since there is nothing to locate images with.
+    pub fn new(flight_code: String, mut positions: Vec<GeoPosition>) -> GTResult<Self> {
+        if positions.is_empty() {
+            return Err(GTError::MissingData(format!(
+                "Flight track for \"{flight_code}\" has no positions."
+            )));
+        }
+
+        positions.sort_by_key(|p| p.timestamp);
+
+        Ok(Self {
+            flight_code,
+            positions,
+            origin: None,
+            destination: None,
+            interpolation: Interpolation::default(),
+            extrapolation: None,
+            dead_reckoning_limit: TimeDelta::zero(),
+        })
+    }
+
+    pub fn with_airports(mut self, origin: Option<Airport>, destination: Option<Airport>) -> Self {
+        self.origin = origin;
+        self.destination = destination;
+        self
+    }
+
+    /// Sets how positions before the first or after the last track point are estimated. Without
+    /// an extrapolation, they are an error. Dead reckoning is only done for up to
+    /// `dead_reckoning_limit` past the end of the track.
+    pub fn with_extrapolation(
+        mut self,
+        extrapolation: Option<Extrapolation>,
+        dead_reckoning_limit: TimeDelta,
+    ) -> Self {
+        self.extrapolation = extrapolation;
+        self.dead_reckoning_limit = dead_reckoning_limit;
+        self
+    }
+
+    /// Sets how positions between track points are estimated.
+    pub fn with_interpolation(mut self, interpolation: Interpolation) -> Self {
+        self.interpolation = interpolation;
+        self
+    }
+
+    pub fn positions(&self) -> &[GeoPosition] {
+        &self.positions
+    }
+
+    /// Estimates when the flight took off, as the last track point before it first climbs clear
+    /// of the altitude it started at. Returns `None` if the track never climbs.
+    pub fn takeoff_time(&self) -> Option<DateTime<Utc>> {
+        // Enough to ride out GPS noise in the elevations of track logs recorded on the ground.
+        const CLIMB_THRESHOLD: i64 = 30;
+
+        let start_altitude = self.positions[0].altitude;
+        let climb = self
+            .positions
+            .iter()
+            .position(|p| p.altitude > start_altitude + CLIMB_THRESHOLD)?;
+
+        Some(self.positions[climb.saturating_sub(1)].timestamp)
+    }
+
+    fn binary_search_positions(&self, timestamp: DateTime<Utc>) -> GTResult<usize> {
+        if timestamp < self.positions[0].timestamp {
+            return Err(GTError::OutOfRange(format!(
+                "Image timestamp '{timestamp}' is older than flight position data. Oldest timestamp: {}",
+                self.positions[0].timestamp
+            )));
+        }
+
+        if timestamp > self.positions[self.positions.len() - 1].timestamp {
+            return Err(GTError::OutOfRange(format!(
+                "Image timestamp '{timestamp}' is newer than flight position data. Newest timestamp: {}",
+                self.positions[self.positions.len() - 1].timestamp
+            )));
+        }
+
+        match self
+            .positions
+            .binary_search_by_key(&timestamp, |p| p.timestamp)
+        {
+            Ok(i) => Ok(i),
+            Err(i) => Ok(i),
+        }
+    }
+
+    /// Estimates the position at `timestamp` outside the span of the track, from the track point
+    /// at the nearer end.
+    fn extrapolate(
+        &self,
+        extrapolation: Extrapolation,
+        timestamp: DateTime<Utc>,
+    ) -> GTResult<TrackFix> {
+        let is_before = timestamp < self.positions[0].timestamp;
+        let (end, next) = if is_before {
+            (self.positions[0], self.positions.get(1))
+        } else {
+            let last = self.positions.len() - 1;
+            (
+                self.positions[last],
+                last.checked_sub(1).map(|i| &self.positions[i]),
+            )
+        };
+
+        let position = match extrapolation {
+            Extrapolation::Clamp => GeoPosition { timestamp, ..end },
+            Extrapolation::DeadReckon => {
+                let elapsed = timestamp - end.timestamp;
+
+                if elapsed.abs() > self.dead_reckoning_limit {
+                    return Err(GTError::OutOfRange(format!(
+                        "Image timestamp '{timestamp}' is {}s outside the flight position data, more than the dead reckoning limit of {}s.",
+                        elapsed.abs().num_seconds(),
+                        self.dead_reckoning_limit.num_seconds()
+                    )));
+                }
+
+                let elapsed_seconds = elapsed.num_milliseconds() as f64 / 1000.0;
+                let (latitude, longitude) = match (end.speed, end.heading, next) {
+                    (Some(speed), Some(heading), _) => GreatCircle::destination(
+                        (end.latitude, end.longitude),
+                        heading,
+                        Converter::knots_to_metres_per_second(speed) * elapsed_seconds,
+                    ),
+                    // Without a recorded velocity, carry on along the last leg of the track.
+                    (_, _, Some(next)) if next.timestamp != end.timestamp => {
+                        let leg_seconds =
+                            (end.timestamp - next.timestamp).num_milliseconds() as f64 / 1000.0;
+
+                        GreatCircle::interpolate(
+                            (next.latitude, next.longitude),
+                            (end.latitude, end.longitude),
+                            1.0 + elapsed_seconds / leg_seconds,
+                        )
+                    }
+                    _ => {
+                        return Err(GTError::MissingData(
+                            "No heading and speed to dead reckon from.".to_string(),
+                        ))
+                    }
+                };
+
+                // The altitude is held, since climbing or descending beyond the track could
+                // easily put the position underground.
+                GeoPosition {
+                    timestamp,
+                    latitude,
+                    longitude,
+                    ..end
+                }
+            }
+            Extrapolation::Airport => {
+                let (airport, name) = if is_before {
+                    (&self.origin, "origin")
+                } else {
+                    (&self.destination, "destination")
+                };
+                let airport = airport.as_ref().ok_or(GTError::MissingData(format!(
+                    "The flight data has no {name} airport to extrapolate to."
+                )))?;
+
+                GeoPosition {
+                    timestamp,
+                    latitude: airport.latitude,
+                    longitude: airport.longitude,
+                    altitude: airport.altitude,
+                    speed: None,
+                    heading: None,
+                    vertical_speed: None,
+                }
+            }
+        };
+
+        // The fix is extrapolated from the leg at the end of the track, so report that leg.
+        let next = next.copied().unwrap_or(end);
+        let (before, after) = if is_before { (end, next) } else { (next, end) };
+
+        Ok(TrackFix {
+            position,
+            before,
+            after,
+        })
+    }
+
+    /// Interpolates the position at `timestamp` between the two track points around it.
+    pub fn get_fix_from_datetime(&self, timestamp: DateTime<Utc>) -> GTResult<TrackFix> {
+        let closest_position = match (self.binary_search_positions(timestamp), self.extrapolation) {
+            (Ok(i), _) => i,
+            (Err(_), Some(extrapolation)) => return self.extrapolate(extrapolation, timestamp),
+            (Err(e), _) => return Err(e),
+        };
+
+        if self.positions[closest_position].timestamp == timestamp {
+            let position = self.positions[closest_position];
+
+            return Ok(TrackFix {
+                position,
+                before: position,
+                after: position,
+            });
+        }
+
+        let interpolated_position = self.interpolation.interpolator().interpolate(
+            &self.positions,
+            closest_position,
+            timestamp,
+        );
+
+        Ok(TrackFix {
+            position: interpolated_position,
+            before: self.positions[closest_position - 1],
+            after: self.positions[closest_position],
+        })
     }
 }
 
 impl Display for FlightGeodata {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(
-            f,
-            "Flight \"{}\", Total positions: {}",
-            self.flight_code,
-            self.positions.len()
-        )
+        write!(f, "Flight \"{}\"", self.flight_code)?;
+
+        if let (Some(origin), Some(destination)) = (&self.origin, &self.destination) {
+            write!(f, " ({} -> {})", origin.code, destination.code)?;
+        }
+
+        write!(f, ", Total positions: {}", self.positions.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{DateTime, TimeDelta, Utc};
+    use exif::{Rational, Tag, Value};
+
+    use super::{FlightGeodata, GeoPosition, TrackFix};
+    use crate::models::interpolator::Extrapolation;
+
+    fn position(seconds: i64, latitude: f64, longitude: f64) -> GeoPosition {
+        GeoPosition {
+            timestamp: DateTime::<Utc>::UNIX_EPOCH + TimeDelta::seconds(seconds),
+            latitude,
+            longitude,
+            altitude: 10_000,
+            speed: None,
+            heading: None,
+            vertical_speed: None,
+        }
+    }
+
+    fn flight(positions: Vec<GeoPosition>) -> FlightGeodata {
+        FlightGeodata::new("TEST1".to_string(), positions).unwrap_or_else(|e| panic!("{e}"))
+    }
+
+    #[test]
+    fn interpolates_across_the_dateline() {
+        let data = flight(vec![position(0, 20.0, 179.9), position(60, 20.0, -179.9)]);
+
+        let fix = data
+            .get_fix_from_datetime(DateTime::<Utc>::UNIX_EPOCH + TimeDelta::seconds(30))
+            .unwrap_or_else(|e| panic!("{e}"));
+
+        assert!(
+            fix.position.longitude.abs() > 179.9,
+            "expected a position near the dateline, got {}",
+            fix.position.longitude
+        );
+        assert!((fix.position.latitude - 20.0).abs() < 0.01);
+    }
+
+    #[test]
+    fn interpolates_over_the_pole() {
+        let data = flight(vec![position(0, 85.0, -90.0), position(600, 85.0, 90.0)]);
+
+        let fix = data
+            .get_fix_from_datetime(DateTime::<Utc>::UNIX_EPOCH + TimeDelta::seconds(300))
+            .unwrap_or_else(|e| panic!("{e}"));
+
+        assert!((fix.position.latitude - 90.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn exact_track_point_is_returned_unchanged() {
+        let data = flight(vec![position(0, 35.0, 14.0), position(60, 36.0, 15.0)]);
+
+        let fix = data
+            .get_fix_from_datetime(DateTime::<Utc>::UNIX_EPOCH + TimeDelta::seconds(60))
+            .unwrap_or_else(|e| panic!("{e}"));
+
+        assert_eq!(fix.position.latitude, 36.0);
+        assert_eq!(fix.position.longitude, 15.0);
+        assert_eq!(fix.gap(), TimeDelta::zero());
+    }
+
+    #[test]
+    fn horizontal_error_grows_away_from_track_points() {
+        let data = flight(vec![position(0, 0.0, 0.0), position(600, 0.0, 1.0)]);
+        let error_at = |seconds| {
+            data.get_fix_from_datetime(DateTime::<Utc>::UNIX_EPOCH + TimeDelta::seconds(seconds))
+                .unwrap_or_else(|e| panic!("{e}"))
+                .horizontal_error()
+        };
+
+        assert_eq!(error_at(0), error_at(600));
+        assert!(error_at(60) > error_at(0));
+        assert!(error_at(300) > error_at(60));
+        assert_eq!(error_at(60), error_at(540));
+    }
+
+    #[test]
+    fn out_of_range_is_an_error_without_extrapolation() {
+        let data = flight(vec![position(0, 0.0, 0.0), position(60, 0.0, 1.0)]);
+
+        assert!(data
+            .get_fix_from_datetime(DateTime::<Utc>::UNIX_EPOCH - TimeDelta::seconds(1))
+            .is_err());
+    }
+
+    #[test]
+    fn clamps_to_the_ends_of_the_track() {
+        let data = flight(vec![position(0, 0.0, 0.0), position(60, 0.0, 1.0)])
+            .with_extrapolation(Some(Extrapolation::Clamp), TimeDelta::zero());
+
+        let fix = data
+            .get_fix_from_datetime(DateTime::<Utc>::UNIX_EPOCH + TimeDelta::seconds(120))
+            .unwrap_or_else(|e| panic!("{e}"));
+
+        assert_eq!(fix.position.longitude, 1.0);
+        assert!(
+            fix.horizontal_error()
+                > data
+                    .get_fix_from_datetime(DateTime::<Utc>::UNIX_EPOCH + TimeDelta::seconds(60))
+                    .unwrap_or_else(|e| panic!("{e}"))
+                    .horizontal_error()
+        );
+    }
+
+    #[test]
+    fn dead_reckons_along_the_last_leg_up_to_the_limit() {
+        let data = flight(vec![position(0, 0.0, 0.0), position(60, 0.0, 1.0)])
+            .with_extrapolation(Some(Extrapolation::DeadReckon), TimeDelta::seconds(60));
+
+        let fix = data
+            .get_fix_from_datetime(DateTime::<Utc>::UNIX_EPOCH - TimeDelta::seconds(30))
+            .unwrap_or_else(|e| panic!("{e}"));
+
+        assert!((fix.position.longitude + 0.5).abs() < 1e-6);
+        assert!(data
+            .get_fix_from_datetime(DateTime::<Utc>::UNIX_EPOCH + TimeDelta::seconds(121))
+            .is_err());
+    }
+
+    #[test]
+    fn track_direction_stays_below_360() {
+        let direction = |heading| {
+            let rational = TrackFix::to_track_direction(heading).unwrap_or_else(|e| panic!("{e}"));
+            f64::from(rational.num) / f64::from(rational.denom)
+        };
+
+        assert_eq!(direction(359.999), 0.0);
+        assert_eq!(direction(360.0), 0.0);
+        assert_eq!(direction(-10.0), 350.0);
+        assert_eq!(direction(725.5), 5.5);
+    }
+
+    #[test]
+    fn takeoff_is_the_last_point_before_the_climb() {
+        let altitudes = [25, 27, 22, 26, 140, 600];
+        let data = flight(
+            altitudes
+                .iter()
+                .enumerate()
+                .map(|(i, &altitude)| GeoPosition {
+                    altitude,
+                    ..position(i as i64 * 10, 51.47, -0.45)
+                })
+                .collect(),
+        );
+
+        assert_eq!(
+            data.takeoff_time(),
+            Some(DateTime::<Utc>::UNIX_EPOCH + TimeDelta::seconds(30))
+        );
+        assert_eq!(flight(vec![position(0, 51.47, -0.45)]).takeoff_time(), None);
+    }
+
+    #[test]
+    fn southern_and_western_fixes_are_written_unsigned_with_refs() {
+        let data = flight(vec![position(0, -33.875, -151.25)]);
+        let fields = data
+            .get_fix_from_datetime(DateTime::<Utc>::UNIX_EPOCH)
+            .and_then(|fix| fix.get_gps_exif())
+            .unwrap_or_else(|e| panic!("{e}"));
+        let value = |tag| {
+            &fields
+                .iter()
+                .find(|f| f.tag == tag)
+                .unwrap_or_else(|| panic!("{tag} missing"))
+                .value
+        };
+        let dms = |tag| match value(tag) {
+            Value::Rational(parts) => parts.iter().map(Rational::to_f64).collect::<Vec<_>>(),
+            other => panic!("{tag} is {other:?}"),
+        };
+
+        assert_eq!(
+            value(Tag::GPSLatitudeRef)
+                .display_as(Tag::GPSLatitudeRef)
+                .to_string(),
+            "S"
+        );
+        assert_eq!(
+            value(Tag::GPSLongitudeRef)
+                .display_as(Tag::GPSLongitudeRef)
+                .to_string(),
+            "W"
+        );
+        assert_eq!(dms(Tag::GPSLatitude), [33.0, 52.0, 30.0]);
+        assert_eq!(dms(Tag::GPSLongitude), [151.0, 15.0, 0.0]);
+    }
+
+    #[test]
+    fn empty_track_is_rejected() {
+        assert!(FlightGeodata::new("TEST1".to_string(), Vec::new()).is_err());
     }
 }
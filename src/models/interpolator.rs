@@ -0,0 +1,369 @@
+use std::fmt::Display;
+
+use chrono::{DateTime, Utc};
+use clap::ValueEnum;
+
+use super::{
+    coord::{Converter, GreatCircle},
+    flight_geodata::GeoPosition,
+};
+
+/// A way of estimating positions between the recorded points of a track.
+pub trait Interpolator {
+    /// Estimates the position at `timestamp`, which lies between `track[index - 1]` and
+    /// `track[index]`. The other points of the track may be used to shape the path.
+    fn interpolate(
+        &self,
+        track: &[GeoPosition],
+        index: usize,
+        timestamp: DateTime<Utc>,
+    ) -> GeoPosition;
+}
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, ValueEnum)]
+pub enum Interpolation {
+    Linear,
+    #[default]
+    GreatCircle,
+    CatmullRom,
+}
+
+impl Interpolation {
+    pub fn interpolator(&self) -> &'static dyn Interpolator {
+        match self {
+            Self::Linear => &LinearInterpolator {},
+            Self::GreatCircle => &GreatCircleInterpolator {},
+            Self::CatmullRom => &CatmullRomInterpolator {},
+        }
+    }
+}
+
+impl Display for Interpolation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let text = match self {
+            Self::Linear => "linear",
+            Self::GreatCircle => "great-circle",
+            Self::CatmullRom => "catmull-rom",
+        };
+        f.write_str(text)
+    }
+}
+
+/// How to place images taken before the first or after the last point of a track.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, ValueEnum)]
+pub enum Extrapolation {
+    /// Use the first or last known position, which suits photos taken at the gate.
+    Clamp,
+    /// Project onwards from the first or last known position along its heading and speed.
+    DeadReckon,
+    /// Use the origin or destination airport.
+    Airport,
+}
+
+impl Display for Extrapolation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let text = match self {
+            Self::Clamp => "clamp",
+            Self::DeadReckon => "dead-reckon",
+            Self::Airport => "airport",
+        };
+        f.write_str(text)
+    }
+}
+
+/// How far `timestamp` is from `lower` towards `higher`, from 0 to 1.
+fn time_factor(lower: &GeoPosition, higher: &GeoPosition, timestamp: DateTime<Utc>) -> f64 {
+    (timestamp - lower.timestamp).num_milliseconds() as f64
+        / (higher.timestamp - lower.timestamp).num_milliseconds() as f64
+}
+
+/// Wraps a longitude difference into -180..180, so it goes the short way round.
+fn wrap_longitude_delta(delta: f64) -> f64 {
+    (delta + 540.0).rem_euclid(360.0) - 180.0
+}
+
+/// Interpolates a value which wraps around at 360, such as a heading, along the shorter way
+/// round the circle.
+fn interpolate_degrees(lower: f64, higher: f64, factor: f64) -> f64 {
+    (lower + factor * wrap_longitude_delta(higher - lower)).rem_euclid(360.0)
+}
+
+fn interpolate_optional(
+    lower: Option<f64>,
+    higher: Option<f64>,
+    interpolate: impl Fn(f64, f64) -> f64,
+) -> Option<f64> {
+    Some(interpolate(lower?, higher?))
+}
+
+/// Interpolates every field of a position linearly. The other interpolators start from this and
+/// replace the fields they handle differently.
+fn blend(lower: &GeoPosition, higher: &GeoPosition, timestamp: DateTime<Utc>) -> GeoPosition {
+    let factor = time_factor(lower, higher, timestamp);
+    let linear = |lower: f64, higher: f64| lower + factor * (higher - lower);
+
+    GeoPosition {
+        timestamp,
+        latitude: linear(lower.latitude, higher.latitude),
+        longitude: interpolate_degrees(lower.longitude + 180.0, higher.longitude + 180.0, factor)
+            - 180.0,
+        altitude: linear(lower.altitude as f64, higher.altitude as f64) as i64,
+        speed: interpolate_optional(lower.speed, higher.speed, linear),
+        heading: interpolate_optional(lower.heading, higher.heading, |lower, higher| {
+            interpolate_degrees(lower, higher, factor)
+        }),
+        vertical_speed: interpolate_optional(lower.vertical_speed, higher.vertical_speed, linear),
+    }
+}
+
+/// Interpolates latitude and longitude in a straight line on the map.
+pub struct LinearInterpolator {}
+
+impl Interpolator for LinearInterpolator {
+    fn interpolate(
+        &self,
+        track: &[GeoPosition],
+        index: usize,
+        timestamp: DateTime<Utc>,
+    ) -> GeoPosition {
+        blend(&track[index - 1], &track[index], timestamp)
+    }
+}
+
+/// Interpolates along the great circle between track points, which is the shortest path.
+pub struct GreatCircleInterpolator {}
+
+impl Interpolator for GreatCircleInterpolator {
+    fn interpolate(
+        &self,
+        track: &[GeoPosition],
+        index: usize,
+        timestamp: DateTime<Utc>,
+    ) -> GeoPosition {
+        let (lower, higher) = (&track[index - 1], &track[index]);
+
+        let (latitude, longitude) = GreatCircle::interpolate(
+            (lower.latitude, lower.longitude),
+            (higher.latitude, higher.longitude),
+            time_factor(lower, higher, timestamp),
+        );
+
+        GeoPosition {
+            latitude,
+            longitude,
+            ..blend(lower, higher, timestamp)
+        }
+    }
+}
+
+/// Interpolates along a cubic Hermite spline, so the path curves through turns and climbs
+/// instead of cutting the corners.
+///
+/// The velocity at each track point is taken from its speed, heading and vertical speed where
+/// the track has them, and otherwise from the neighbouring points as in a Catmull-Rom spline.
+pub struct CatmullRomInterpolator {}
+
+/// A track point in metres east and north of a reference point, and metres of altitude.
+#[derive(Clone, Copy)]
+struct LocalPoint {
+    east: f64,
+    north: f64,
+    up: f64,
+}
+
+impl CatmullRomInterpolator {
+    fn metres_per_degree() -> f64 {
+        GreatCircle::EARTH_RADIUS_METRES * std::f64::consts::PI / 180.0
+    }
+
+    /// Projects `position` onto a flat plane around `origin`, which is accurate enough over the
+    /// span of a few track points.
+    fn to_local(origin: &GeoPosition, position: &GeoPosition) -> LocalPoint {
+        let east_scale = Self::metres_per_degree() * origin.latitude.to_radians().cos();
+
+        LocalPoint {
+            east: wrap_longitude_delta(position.longitude - origin.longitude) * east_scale,
+            north: (position.latitude - origin.latitude) * Self::metres_per_degree(),
+            up: position.altitude as f64,
+        }
+    }
+
+    fn from_local(origin: &GeoPosition, point: LocalPoint) -> (f64, f64) {
+        let east_scale = Self::metres_per_degree() * origin.latitude.to_radians().cos();
+
+        let latitude = origin.latitude + point.north / Self::metres_per_degree();
+        let longitude = if east_scale.abs() > f64::EPSILON {
+            wrap_longitude_delta(origin.longitude + point.east / east_scale)
+        } else {
+            origin.longitude
+        };
+
+        (latitude.clamp(-90.0, 90.0), longitude)
+    }
+
+    fn seconds_between(a: &GeoPosition, b: &GeoPosition) -> f64 {
+        (b.timestamp - a.timestamp).num_milliseconds() as f64 / 1000.0
+    }
+
+    /// Estimates the velocity in metres per second at `track[i]`.
+    fn velocity(origin: &GeoPosition, track: &[GeoPosition], i: usize) -> LocalPoint {
+        let before = &track[i.saturating_sub(1)];
+        let after = &track[(i + 1).min(track.len() - 1)];
+
+        // Finite difference over the neighbouring points, or one-sided at the ends of the track.
+        let (a, b) = (
+            Self::to_local(origin, before),
+            Self::to_local(origin, after),
+        );
+        let seconds = Self::seconds_between(before, after);
+        let mut velocity = if seconds > 0.0 {
+            LocalPoint {
+                east: (b.east - a.east) / seconds,
+                north: (b.north - a.north) / seconds,
+                up: (b.up - a.up) / seconds,
+            }
+        } else {
+            LocalPoint {
+                east: 0.0,
+                north: 0.0,
+                up: 0.0,
+            }
+        };
+
+        let point = &track[i];
+        if let (Some(speed), Some(heading)) = (point.speed, point.heading) {
+            let speed = Converter::knots_to_metres_per_second(speed);
+            velocity.east = speed * heading.to_radians().sin();
+            velocity.north = speed * heading.to_radians().cos();
+        }
+
+        if let Some(vertical_speed) = point.vertical_speed {
+            velocity.up = Converter::feet_to_metres(vertical_speed) / 60.0;
+        }
+
+        velocity
+    }
+}
+
+impl Interpolator for CatmullRomInterpolator {
+    fn interpolate(
+        &self,
+        track: &[GeoPosition],
+        index: usize,
+        timestamp: DateTime<Utc>,
+    ) -> GeoPosition {
+        let (lower, higher) = (&track[index - 1], &track[index]);
+        let t = time_factor(lower, higher, timestamp);
+        let seconds = Self::seconds_between(lower, higher);
+
+        let p0 = Self::to_local(lower, lower);
+        let p1 = Self::to_local(lower, higher);
+        let v0 = Self::velocity(lower, track, index - 1);
+        let v1 = Self::velocity(lower, track, index);
+
+        // Cubic Hermite basis functions, with the tangents scaled to the length of the segment.
+        let h00 = 2.0 * t.powi(3) - 3.0 * t.powi(2) + 1.0;
+        let h10 = t.powi(3) - 2.0 * t.powi(2) + t;
+        let h01 = -2.0 * t.powi(3) + 3.0 * t.powi(2);
+        let h11 = t.powi(3) - t.powi(2);
+        let hermite = |p0: f64, v0: f64, p1: f64, v1: f64| {
+            h00 * p0 + h10 * seconds * v0 + h01 * p1 + h11 * seconds * v1
+        };
+
+        let point = LocalPoint {
+            east: hermite(p0.east, v0.east, p1.east, v1.east),
+            north: hermite(p0.north, v0.north, p1.north, v1.north),
+            up: hermite(p0.up, v0.up, p1.up, v1.up),
+        };
+        let (latitude, longitude) = Self::from_local(lower, point);
+
+        GeoPosition {
+            latitude,
+            longitude,
+            altitude: point.up.round() as i64,
+            ..blend(lower, higher, timestamp)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{DateTime, TimeDelta, Utc};
+
+    use super::{interpolate_degrees, Interpolation};
+    use crate::models::flight_geodata::GeoPosition;
+
+    fn time(seconds: i64) -> DateTime<Utc> {
+        DateTime::<Utc>::UNIX_EPOCH + TimeDelta::seconds(seconds)
+    }
+
+    fn position(seconds: i64, latitude: f64, longitude: f64) -> GeoPosition {
+        GeoPosition {
+            timestamp: time(seconds),
+            latitude,
+            longitude,
+            altitude: 10_000,
+            speed: None,
+            heading: None,
+            vertical_speed: None,
+        }
+    }
+
+    #[test]
+    fn linear_wraps_longitude_across_the_dateline() {
+        let track = [position(0, 0.0, 179.0), position(60, 0.0, -179.0)];
+
+        let p = Interpolation::Linear
+            .interpolator()
+            .interpolate(&track, 1, time(15));
+
+        assert!((p.longitude - 179.5).abs() < 1e-9, "got {}", p.longitude);
+    }
+
+    #[test]
+    fn headings_interpolate_the_short_way_through_north() {
+        assert_eq!(interpolate_degrees(350.0, 10.0, 0.5), 0.0);
+        assert_eq!(interpolate_degrees(10.0, 350.0, 0.25), 5.0);
+        assert_eq!(interpolate_degrees(350.0, 10.0, 0.25), 355.0);
+    }
+
+    #[test]
+    fn catmull_rom_follows_a_straight_track() {
+        let track = [
+            position(0, 0.0, 0.0),
+            position(60, 0.0, 0.1),
+            position(120, 0.0, 0.2),
+            position(180, 0.0, 0.3),
+        ];
+
+        let p = Interpolation::CatmullRom
+            .interpolator()
+            .interpolate(&track, 2, time(90));
+
+        assert!(p.latitude.abs() < 1e-9, "got {}", p.latitude);
+        assert!((p.longitude - 0.15).abs() < 1e-6, "got {}", p.longitude);
+    }
+
+    #[test]
+    fn catmull_rom_curves_through_a_turn() {
+        // Heading north, then turning to head east. The path should bow out to the north-west of
+        // the straight line between the two points.
+        let mut start = position(0, 0.0, 0.0);
+        start.speed = Some(400.0);
+        start.heading = Some(0.0);
+        let mut end = position(60, 0.05, 0.05);
+        end.speed = Some(400.0);
+        end.heading = Some(90.0);
+        let track = [start, end];
+
+        let curved = Interpolation::CatmullRom
+            .interpolator()
+            .interpolate(&track, 1, time(30));
+        let straight = Interpolation::Linear
+            .interpolator()
+            .interpolate(&track, 1, time(30));
+
+        assert!(curved.latitude > straight.latitude);
+        assert!(curved.longitude < straight.longitude);
+    }
+}
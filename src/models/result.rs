@@ -7,9 +7,16 @@ pub type GTResult<T> = Result<T, GTError>;
 pub enum GTError {
     Parser,
     MissingData(String),
+    /// An image has no EXIF data, or none giving its capture time.
+    MissingExif(String),
+    /// A time falls outside the flight track, or a gap in it too long to interpolate across.
+    OutOfRange(String),
     InvalidData(String),
     Io(std::io::Error),
     Serde(String),
+    Xml(roxmltree::Error),
+    Csv(csv::Error),
+    Zip(zip::result::ZipError),
     Args(String),
     HtmlSelection(String),
     Reqwest(reqwest::Error),
@@ -66,17 +73,40 @@ impl From<serde_json::Error> for GTError {
     }
 }
 
+impl From<roxmltree::Error> for GTError {
+    fn from(value: roxmltree::Error) -> Self {
+        GTError::Xml(value)
+    }
+}
+
+impl From<csv::Error> for GTError {
+    fn from(value: csv::Error) -> Self {
+        GTError::Csv(value)
+    }
+}
+
+impl From<zip::result::ZipError> for GTError {
+    fn from(value: zip::result::ZipError) -> Self {
+        GTError::Zip(value)
+    }
+}
+
 impl Display for GTError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             Self::Parser => write!(f, "JSON Parser error."),
             Self::MissingData(e) => write!(f, "Missing data error: {e}"),
+            Self::MissingExif(e) => write!(f, "Missing EXIF error: {e}"),
+            Self::OutOfRange(e) => write!(f, "Out of range error: {e}"),
             Self::Reqwest(e) => write!(f, "HTTP client error: {e}"),
             Self::HtmlSelection(e) => write!(f, "HTML selection error: {e}"),
             Self::InvalidData(e) => write!(f, "Invalid data error: {e}"),
             Self::ImgHandling(e) => write!(f, "Image-handling error: {e}"),
             Self::Io(e) => write!(f, "IO parser error: {e}"),
             Self::Serde(e) => write!(f, "Serde error: {e}"),
+            Self::Xml(e) => write!(f, "XML parser error: {e}"),
+            Self::Csv(e) => write!(f, "CSV parser error: {e}"),
+            Self::Zip(e) => write!(f, "Zip archive error: {e}"),
             Self::Args(e) => write!(f, "CLI args config error: {e}"),
             Self::Exif(e) => write!(f, "Exif-related error: {e}"),
             Self::Conversion(e) => write!(f, "Data conversion error: {e}"),
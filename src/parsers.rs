@@ -1 +1,11 @@
+pub mod adsbx_parser;
+pub mod csv_parser;
+pub mod flight_history_parser;
+pub mod flight_list_parser;
+pub mod geojson_parser;
+pub mod gpx_parser;
+pub mod igc_parser;
 pub mod json_parser;
+pub mod kml_parser;
+pub mod opensky_parser;
+pub mod track_parser;
@@ -0,0 +1,177 @@
+use chrono::DateTime;
+use serde_json::Value;
+
+use crate::models::{
+    coord::Converter,
+    flight_geodata::{FlightGeodata, GeoPosition},
+    result::{GTError, GTResult},
+};
+
+use super::{
+    json_parser::JsonParser,
+    track_parser::{fill_altitudes, TrackParser, TrackSource},
+};
+
+/// Parses flight geodata from an ADS-B Exchange / readsb globe history trace
+/// (`trace_full_<icao>.json`).
+///
+/// Each `trace` entry is `[dt, lat, lon, alt_ft, gs_kts, track, flags, vrate_fpm, ...]`, where
+/// `dt` is the number of seconds since the top-level `timestamp`. Altitudes are barometric feet,
+/// or the string `"ground"`. Ground reports give no field elevation, so they are taken as 0 m.
+pub struct AdsbExchangeTraceParser {}
+
+impl JsonParser for AdsbExchangeTraceParser {
+    fn try_parse_geodata(&self, src: Value) -> GTResult<FlightGeodata> {
+        let base_timestamp =
+            src.get("timestamp")
+                .and_then(Value::as_f64)
+                .ok_or(GTError::MissingData(
+                    "Trace has no base timestamp.".to_string(),
+                ))?;
+        let trace = src
+            .get("trace")
+            .and_then(Value::as_array)
+            .ok_or(GTError::MissingData(
+                "Trace has no trace array.".to_string(),
+            ))?;
+
+        let mut altitudes = Vec::with_capacity(trace.len());
+        let mut positions = Vec::with_capacity(trace.len());
+        for (i, entry) in trace.iter().enumerate() {
+            let entry = entry.as_array().ok_or(GTError::InvalidData(format!(
+                "Trace entry {i} is not an array."
+            )))?;
+            let field = |index: usize, name: &str| {
+                entry
+                    .get(index)
+                    .and_then(Value::as_f64)
+                    .ok_or(GTError::InvalidData(format!(
+                        "Trace entry {i} has a missing or invalid {name}."
+                    )))
+            };
+
+            let offset = field(0, "time offset")?;
+            let timestamp = DateTime::from_timestamp_millis(
+                ((base_timestamp + offset) * 1000.0).round() as i64,
+            )
+            .ok_or(GTError::Conversion(format!(
+                "Trace entry {i} has an out of range timestamp."
+            )))?;
+
+            altitudes.push(match entry.get(3) {
+                Some(Value::Number(_)) => {
+                    Some(Converter::feet_to_metres(field(3, "altitude")?).round() as i64)
+                }
+                Some(Value::String(s)) if s == "ground" => Some(0),
+                _ => None,
+            });
+
+            positions.push(GeoPosition {
+                timestamp,
+                latitude: field(1, "latitude")?,
+                longitude: field(2, "longitude")?,
+                altitude: 0,
+                speed: entry.get(4).and_then(Value::as_f64),
+                heading: entry.get(5).and_then(Value::as_f64),
+                vertical_speed: entry.get(7).and_then(Value::as_f64),
+            });
+        }
+
+        if positions.is_empty() {
+            return Err(GTError::MissingData(
+                "Trace has no position entries.".to_string(),
+            ));
+        }
+
+        fill_altitudes(&mut positions, &altitudes);
+
+        let flight_code = self.get_flight_code(&src, trace).unwrap_or_default();
+
+        FlightGeodata::new(flight_code, positions)
+    }
+}
+
+impl TrackParser for AdsbExchangeTraceParser {
+    fn format_name(&self) -> &'static str {
+        "ADS-B Exchange trace JSON"
+    }
+
+    fn sniff(&self, src: &TrackSource) -> bool {
+        src.json()
+            .is_some_and(|v| v.get("icao").is_some() && v.get("trace").is_some_and(Value::is_array))
+    }
+
+    fn parse_track(&self, src: TrackSource) -> GTResult<FlightGeodata> {
+        self.try_parse_geodata(src.into_json()?)
+    }
+}
+
+impl AdsbExchangeTraceParser {
+    /// Uses the first callsign found in the trace's aircraft details (index 8 of an entry),
+    /// falling back to the registration and then the ICAO hex address.
+    fn get_flight_code(&self, src: &Value, trace: &[Value]) -> Option<String> {
+        let callsign = trace.iter().find_map(|entry| {
+            let callsign = entry.get(8)?.get("flight")?.as_str()?.trim();
+            (!callsign.is_empty()).then_some(callsign)
+        });
+
+        callsign
+            .or_else(|| src.get("r")?.as_str())
+            .or_else(|| src.get("icao")?.as_str())
+            .map(str::to_string)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::AdsbExchangeTraceParser;
+    use crate::parsers::json_parser::JsonParser;
+
+    #[test]
+    fn parses_offsets_feet_and_ground_reports() {
+        let src = json!({
+            "icao": "4ca7b3",
+            "r": "EI-ABC",
+            "timestamp": 1704103200.5,
+            "trace": [
+                [0, 53.42, -6.27, "ground", 12, 280, 0, null],
+                [30.5, 53.43, -6.31, 1000, 160, 281, 0, 1500, { "flight": "RYR12AB " }],
+                [60, 53.44, -6.39, null, 180, 283, 0, 1600],
+                [3600, 51.47, -0.45, "ground", 20, 90, 0, null],
+            ],
+        });
+
+        let data = AdsbExchangeTraceParser {}
+            .try_parse_geodata(src)
+            .unwrap_or_else(|e| panic!("{e}"));
+        let positions = data.positions();
+
+        assert_eq!(data.flight_code, "RYR12AB");
+        assert_eq!(positions[1].timestamp.timestamp_millis(), 1704103231000);
+        assert_eq!(positions[1].speed, Some(160.0));
+        assert_eq!(positions[1].vertical_speed, Some(1500.0));
+        // Ground reports are 0 m, and 1000 ft is held through the missing report.
+        assert_eq!(
+            positions.iter().map(|p| p.altitude).collect::<Vec<_>>(),
+            [0, 305, 305, 0]
+        );
+    }
+
+    #[test]
+    fn flight_code_falls_back_to_the_registration() {
+        let src = json!({
+            "icao": "4ca7b3",
+            "r": "EI-ABC",
+            "timestamp": 1704103200,
+            "trace": [[0, 53.42, -6.27, 1000]],
+        });
+
+        let data = AdsbExchangeTraceParser {}
+            .try_parse_geodata(src)
+            .unwrap_or_else(|e| panic!("{e}"));
+
+        assert_eq!(data.flight_code, "EI-ABC");
+    }
+}
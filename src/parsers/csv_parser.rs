@@ -0,0 +1,194 @@
+use std::io::Read;
+
+use chrono::DateTime;
+use csv::StringRecord;
+
+use crate::models::{
+    coord::Converter,
+    flight_geodata::{FlightGeodata, GeoPosition},
+    result::{GTError, GTResult},
+};
+
+use super::track_parser::{first_line, TrackParser, TrackSource};
+
+/// Parses flight geodata from the CSV export offered by the FlightRadar24 website
+/// (`Timestamp,UTC,Callsign,Position,Altitude,Speed,Direction`).
+pub struct FlightRadar24CsvParser {}
+
+impl TrackParser for FlightRadar24CsvParser {
+    fn format_name(&self) -> &'static str {
+        "FlightRadar24 CSV"
+    }
+
+    fn sniff(&self, src: &TrackSource) -> bool {
+        first_line(src.bytes()).is_some_and(|header| {
+            let columns = header
+                .split(',')
+                .map(|c| c.trim().trim_matches('"'))
+                .collect::<Vec<_>>();
+
+            ["Timestamp", "Position", "Altitude"]
+                .iter()
+                .all(|name| columns.iter().any(|c| c.eq_ignore_ascii_case(name)))
+        })
+    }
+
+    fn parse_track(&self, src: TrackSource) -> GTResult<FlightGeodata> {
+        self.try_parse_geodata(src.bytes())
+    }
+}
+
+struct ColumnIndices {
+    timestamp: usize,
+    callsign: usize,
+    position: usize,
+    altitude: usize,
+    speed: Option<usize>,
+    direction: Option<usize>,
+}
+
+impl FlightRadar24CsvParser {
+    pub fn try_parse_geodata<R: Read>(&self, src: R) -> GTResult<FlightGeodata> {
+        let mut reader = csv::ReaderBuilder::new()
+            .trim(csv::Trim::All)
+            .from_reader(src);
+        let columns = self.get_column_indices(reader.headers()?)?;
+
+        let mut flight_code = None;
+        let mut positions = Vec::new();
+        for (i, record) in reader.records().enumerate() {
+            let record = record?;
+            // Line 1 is the header row.
+            let line = i + 2;
+
+            if flight_code.is_none() {
+                flight_code = record
+                    .get(columns.callsign)
+                    .filter(|c| !c.is_empty())
+                    .map(str::to_string);
+            }
+
+            positions.push(self.get_geoposition(&record, &columns, line)?);
+        }
+
+        if positions.is_empty() {
+            return Err(GTError::MissingData(
+                "No rows found in FlightRadar24 CSV export.".to_string(),
+            ));
+        }
+
+        FlightGeodata::new(flight_code.unwrap_or_default(), positions)
+    }
+
+    fn get_column_indices(&self, headers: &StringRecord) -> GTResult<ColumnIndices> {
+        let find = |name: &str| {
+            headers
+                .iter()
+                .position(|h| h.eq_ignore_ascii_case(name))
+                .ok_or(GTError::MissingData(format!(
+                    "CSV export has no '{name}' column."
+                )))
+        };
+
+        Ok(ColumnIndices {
+            timestamp: find("Timestamp")?,
+            callsign: find("Callsign")?,
+            position: find("Position")?,
+            altitude: find("Altitude")?,
+            speed: find("Speed").ok(),
+            direction: find("Direction").ok(),
+        })
+    }
+
+    fn get_geoposition(
+        &self,
+        record: &StringRecord,
+        columns: &ColumnIndices,
+        line: usize,
+    ) -> GTResult<GeoPosition> {
+        let invalid = |column: &str| {
+            GTError::InvalidData(format!(
+                "Missing or invalid '{column}' value on line {line} of CSV export."
+            ))
+        };
+
+        let timestamp = record
+            .get(columns.timestamp)
+            .and_then(|v| v.parse::<i64>().ok())
+            .and_then(|v| DateTime::from_timestamp(v, 0))
+            .ok_or_else(|| invalid("Timestamp"))?;
+
+        // The position column is a single quoted "lat,lon" field.
+        let (latitude, longitude) = record
+            .get(columns.position)
+            .and_then(|v| v.split_once(','))
+            .and_then(|(lat, lon)| Some((lat.trim().parse().ok()?, lon.trim().parse().ok()?)))
+            .ok_or_else(|| invalid("Position"))?;
+
+        let altitude_ft = record
+            .get(columns.altitude)
+            .and_then(|v| v.parse::<f64>().ok())
+            .ok_or_else(|| invalid("Altitude"))?;
+
+        let optional = |column: Option<usize>| {
+            column
+                .and_then(|c| record.get(c))
+                .and_then(|v| v.parse::<f64>().ok())
+        };
+
+        Ok(GeoPosition {
+            timestamp,
+            latitude,
+            longitude,
+            altitude: Converter::feet_to_metres(altitude_ft).round() as i64,
+            speed: optional(columns.speed),
+            heading: optional(columns.direction),
+            vertical_speed: None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::FlightRadar24CsvParser;
+    use crate::parsers::track_parser::{TrackParser, TrackSource};
+
+    const EXPORT: &str = "Timestamp,UTC,Callsign,Position,Altitude,Speed,Direction
+1704103200,2024-01-01T10:00:00Z,BAW123,\"51.470000,-0.454300\",0,12,270
+1704103260,2024-01-01T10:01:00Z,BAW123,\"51.480000,-0.500000\",1000,160,275
+1704103320,2024-01-01T10:02:00Z,BAW123,\"51.490000,-0.550000\",10000,250,
+";
+
+    #[test]
+    fn parses_quoted_positions_and_converts_feet() {
+        let data = FlightRadar24CsvParser {}
+            .try_parse_geodata(EXPORT.as_bytes())
+            .unwrap_or_else(|e| panic!("{e}"));
+        let positions = data.positions();
+
+        assert_eq!(data.flight_code, "BAW123");
+        assert_eq!(positions.len(), 3);
+        assert_eq!(positions[1].latitude, 51.48);
+        assert_eq!(positions[1].longitude, -0.5);
+        assert_eq!(positions[1].altitude, 305);
+        assert_eq!(positions[2].altitude, 3048);
+        assert_eq!(positions[1].speed, Some(160.0));
+        assert_eq!(positions[1].heading, Some(275.0));
+        assert_eq!(positions[2].heading, None);
+    }
+
+    #[test]
+    fn unquoted_position_is_an_error() {
+        let src = EXPORT.replace("\"51.480000,-0.500000\"", "51.48");
+
+        assert!(FlightRadar24CsvParser {}
+            .try_parse_geodata(src.as_bytes())
+            .is_err());
+    }
+
+    #[test]
+    fn sniffs_the_export_header() {
+        assert!(FlightRadar24CsvParser {}.sniff(&TrackSource::new(EXPORT.as_bytes())));
+        assert!(!FlightRadar24CsvParser {}.sniff(&TrackSource::new(b"time,lat,lon\n")));
+    }
+}
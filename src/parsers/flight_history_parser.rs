@@ -0,0 +1,146 @@
+use chrono::{DateTime, NaiveDate, Utc};
+use scraper::{ElementRef, Html, Selector};
+
+use crate::models::{
+    flight_candidate::FlightCandidate,
+    result::{GTError, GTResult},
+};
+
+/// Parses the candidate flights from the flight history table of a FlightRadar24
+/// `/data/flights/<code>` page.
+pub struct FlightRadar24FlightHistoryParser {}
+
+impl FlightRadar24FlightHistoryParser {
+    const FLIGHTS_TABLE_SELECTOR: &'static str = "#tbl-datatable tbody";
+    const PLAYBACK_BTN_SELECTOR: &'static str = ".btn-playback";
+    const AIRPORT_LINK_SELECTOR: &'static str = "a[href^='/data/airports/']";
+    const TIMESTAMP_CELL_SELECTOR: &'static str = "td[data-timestamp]";
+
+    /// Returns every row of the table which departs on `dod`. Rows without playback data, such as
+    /// scheduled or cancelled flights, are skipped.
+    pub fn try_parse_candidates(
+        &self,
+        flight_code: &str,
+        src: &str,
+        dod: NaiveDate,
+    ) -> GTResult<Vec<FlightCandidate>> {
+        let dom = Html::parse_document(src);
+
+        let table_selector = Selector::parse(Self::FLIGHTS_TABLE_SELECTOR)?;
+        let Some(table) = dom.select(&table_selector).next() else {
+            return Err(GTError::HtmlSelection(format!(
+                "Could not find flight history table ({})",
+                Self::FLIGHTS_TABLE_SELECTOR
+            )));
+        };
+
+        let format = dod.format("%d %b %Y").to_string();
+        eprintln!("Checking for rows with date of departure '{format}'");
+
+        let mut candidates = Vec::new();
+        for row in table
+            .child_elements()
+            .filter(|tr| tr.text().any(|t| t.contains(&format)))
+        {
+            candidates.extend(self.get_candidate(flight_code, row)?);
+        }
+
+        Ok(candidates)
+    }
+
+    /// Returns `None` for a row without a playback button, as it has no track to download.
+    fn get_candidate(
+        &self,
+        flight_code: &str,
+        row: ElementRef,
+    ) -> GTResult<Option<FlightCandidate>> {
+        let playback_btn_selector = Selector::parse(Self::PLAYBACK_BTN_SELECTOR)?;
+        let Some(playback_btn) = row.select(&playback_btn_selector).next() else {
+            return Ok(None);
+        };
+
+        let Some(hex) = playback_btn.attr("data-flight-hex") else {
+            return Err(GTError::HtmlSelection(
+                "No data-flight-hex found in expected location.".to_string(),
+            ));
+        };
+
+        let Some(data_timestamp) = playback_btn.attr("data-timestamp") else {
+            return Err(GTError::HtmlSelection(
+                "No data-timestamp found in expected location.".to_string(),
+            ));
+        };
+
+        // The scheduled times are in the STD, ATD and STA columns, in that order.
+        let timestamp_selector = Selector::parse(Self::TIMESTAMP_CELL_SELECTOR)?;
+        let times = row
+            .select(&timestamp_selector)
+            .filter_map(|td| Self::parse_timestamp(td.attr("data-timestamp")?))
+            .collect::<Vec<_>>();
+
+        let airport_selector = Selector::parse(Self::AIRPORT_LINK_SELECTOR)?;
+        let mut airports = row
+            .select(&airport_selector)
+            .map(|a| a.text().collect::<String>().trim().to_string());
+
+        Ok(Some(FlightCandidate {
+            flight_id: hex.to_string(),
+            flight_code: flight_code.to_string(),
+            scheduled_departure: times.first().copied(),
+            scheduled_arrival: times.last().copied().filter(|_| times.len() > 1),
+            actual_departure: Self::parse_timestamp(data_timestamp),
+            actual_arrival: None,
+            origin: airports.next(),
+            destination: airports.next(),
+        }))
+    }
+
+    fn parse_timestamp(src: &str) -> Option<DateTime<Utc>> {
+        DateTime::from_timestamp(src.trim().parse().ok()?, 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use super::FlightRadar24FlightHistoryParser;
+
+    const PAGE: &str = r#"<html><body><table id="tbl-datatable"><tbody>
+<tr>
+  <td>01 Jan 2024</td>
+  <td><a href="/data/airports/lhr">LHR</a></td>
+  <td><a href="/data/airports/mla">MLA</a></td>
+  <td data-timestamp="1704103200"></td>
+  <td data-timestamp="1704114000"></td>
+  <td><a class="btn-playback" data-flight-hex="3a1b2c" data-timestamp="1704103500"></a></td>
+</tr>
+<tr>
+  <td>01 Jan 2024</td>
+  <td><a href="/data/airports/lhr">LHR</a></td>
+  <td><a href="/data/airports/mla">MLA</a></td>
+  <td data-timestamp="1704146400"></td>
+  <td>Scheduled</td>
+</tr>
+<tr>
+  <td>02 Jan 2024</td>
+  <td><a class="btn-playback" data-flight-hex="3a1b99" data-timestamp="1704189900"></a></td>
+</tr>
+</tbody></table></body></html>"#;
+
+    #[test]
+    fn rows_without_playback_are_skipped() {
+        let candidates = FlightRadar24FlightHistoryParser {}
+            .try_parse_candidates("BA123", PAGE, NaiveDate::from_ymd_opt(2024, 1, 1).unwrap())
+            .unwrap_or_else(|e| panic!("{e}"));
+
+        assert_eq!(candidates.len(), 1);
+        assert_eq!(candidates[0].flight_id, "3a1b2c");
+        assert_eq!(candidates[0].origin.as_deref(), Some("LHR"));
+        assert_eq!(candidates[0].destination.as_deref(), Some("MLA"));
+        assert_eq!(
+            candidates[0].actual_departure.map(|t| t.timestamp()),
+            Some(1704103500)
+        );
+    }
+}
@@ -0,0 +1,114 @@
+use chrono::{DateTime, Utc};
+use serde_json::Value;
+
+use crate::models::{
+    flight_candidate::FlightCandidate,
+    result::{GTError, GTResult},
+};
+
+/// Parses the candidate flights from a FlightRadar24 `flight/list.json` response.
+pub struct FlightRadar24FlightListParser {}
+
+impl FlightRadar24FlightListParser {
+    pub fn try_parse_candidates(&self, src: &Value) -> GTResult<Vec<FlightCandidate>> {
+        let data = src
+            .pointer("/result/response/data")
+            .ok_or(GTError::Parser)?;
+
+        // The list is null rather than empty when the flight code is unknown.
+        let Some(data) = data.as_array() else {
+            return Ok(Vec::new());
+        };
+
+        // Flights which haven't been assigned an id yet (scheduled too far ahead) have no
+        // playback data, so they are not candidates.
+        Ok(data.iter().filter_map(|f| self.get_candidate(f)).collect())
+    }
+
+    fn get_candidate(&self, src: &Value) -> Option<FlightCandidate> {
+        let time = |pointer: &str| -> Option<DateTime<Utc>> {
+            DateTime::from_timestamp(src.pointer(pointer)?.as_i64()?, 0)
+        };
+        let airport = |pointer: &str| -> Option<String> {
+            let code = src.pointer(pointer)?;
+            ["iata", "icao"]
+                .iter()
+                .find_map(|key| code.get(key)?.as_str())
+                .map(str::to_string)
+        };
+
+        Some(FlightCandidate {
+            flight_id: src.pointer("/identification/id")?.as_str()?.to_string(),
+            flight_code: src
+                .pointer("/identification/number/default")?
+                .as_str()?
+                .to_string(),
+            scheduled_departure: time("/time/scheduled/departure"),
+            scheduled_arrival: time("/time/scheduled/arrival"),
+            actual_departure: time("/time/real/departure"),
+            actual_arrival: time("/time/real/arrival"),
+            origin: airport("/airport/origin/code"),
+            destination: airport("/airport/destination/code"),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::FlightRadar24FlightListParser;
+
+    const FIXTURE: &str = include_str!("../../tests/fixtures/fr24_flight_list.json");
+
+    #[test]
+    fn parses_the_listed_flights() {
+        let candidates = FlightRadar24FlightListParser {}
+            .try_parse_candidates(&serde_json::from_str(FIXTURE).unwrap())
+            .unwrap_or_else(|e| panic!("{e}"));
+
+        // The flight without an id has no playback data yet.
+        assert_eq!(candidates.len(), 2);
+
+        let operated = &candidates[0];
+        assert_eq!(operated.flight_id, "3a1b2c4d");
+        assert_eq!(operated.flight_code, "BA123");
+        assert_eq!(operated.origin.as_deref(), Some("LHR"));
+        assert_eq!(operated.destination.as_deref(), Some("MLA"));
+        assert_eq!(
+            operated.departure().map(|t| t.timestamp()),
+            Some(1704103800)
+        );
+        assert_eq!(operated.arrival().map(|t| t.timestamp()), Some(1704114300));
+
+        // Scheduled times stand in for missing actual ones, and ICAO codes for IATA ones.
+        let scheduled = &candidates[1];
+        assert_eq!(scheduled.flight_id, "3a1b9f00");
+        assert_eq!(scheduled.origin.as_deref(), Some("EGLL"));
+        assert_eq!(scheduled.destination, None);
+        assert_eq!(
+            scheduled.departure().map(|t| t.timestamp()),
+            Some(1704146400)
+        );
+    }
+
+    #[test]
+    fn unknown_flight_code_has_no_candidates() {
+        let src = json!({ "result": { "response": { "data": null } } });
+
+        let candidates = FlightRadar24FlightListParser {}
+            .try_parse_candidates(&src)
+            .unwrap_or_else(|e| panic!("{e}"));
+
+        assert!(candidates.is_empty());
+    }
+
+    #[test]
+    fn response_without_data_is_an_error() {
+        let src = json!({ "result": { "response": {} } });
+
+        assert!(FlightRadar24FlightListParser {}
+            .try_parse_candidates(&src)
+            .is_err());
+    }
+}
@@ -0,0 +1,306 @@
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde_json::{json, Value};
+
+use crate::models::{
+    flight_geodata::{FlightGeodata, GeoPosition},
+    result::{GTError, GTResult},
+};
+
+use super::{
+    json_parser::JsonParser,
+    track_parser::{TrackParser, TrackSource},
+};
+
+/// Parses flight geodata from GeoJSON, and writes it back out again.
+///
+/// Tracks can be given either as a `LineString`/`MultiPoint` whose feature has a `coordTimes` (or
+/// `times`) property with one time per coordinate, or as a `FeatureCollection` of `Point` features
+/// which each have a `time` (or `timestamp`) property. Times may be RFC 3339 strings or Unix
+/// timestamps in seconds or milliseconds.
+pub struct GeoJsonParser {}
+
+impl JsonParser for GeoJsonParser {
+    fn try_parse_geodata(&self, src: Value) -> GTResult<FlightGeodata> {
+        let mut flight_code = self.get_flight_code(&src);
+        let mut positions = Vec::new();
+
+        match src.get("type").and_then(Value::as_str) {
+            Some("FeatureCollection") => {
+                let features =
+                    src.get("features")
+                        .and_then(Value::as_array)
+                        .ok_or(GTError::MissingData(
+                            "FeatureCollection has no features array.".to_string(),
+                        ))?;
+
+                for feature in features {
+                    flight_code = flight_code.or_else(|| self.get_flight_code(feature));
+                    positions.extend(self.get_feature_positions(feature)?);
+                }
+            }
+            Some("Feature") => positions.extend(self.get_feature_positions(&src)?),
+            Some(_) => positions.extend(self.get_geometry_positions(&src, None)?),
+            None => {
+                return Err(GTError::InvalidData(
+                    "GeoJSON object has no type.".to_string(),
+                ))
+            }
+        }
+
+        if positions.is_empty() {
+            return Err(GTError::MissingData(
+                "No timestamped positions found in GeoJSON.".to_string(),
+            ));
+        }
+
+        FlightGeodata::new(flight_code.unwrap_or_default(), positions)
+    }
+}
+
+impl TrackParser for GeoJsonParser {
+    fn format_name(&self) -> &'static str {
+        "GeoJSON"
+    }
+
+    fn sniff(&self, src: &TrackSource) -> bool {
+        src.json().is_some_and(|v| {
+            matches!(
+                v.get("type").and_then(Value::as_str),
+                Some("FeatureCollection" | "Feature" | "LineString" | "MultiPoint" | "Point")
+            )
+        })
+    }
+
+    fn parse_track(&self, src: TrackSource) -> GTResult<FlightGeodata> {
+        self.try_parse_geodata(src.into_json()?)
+    }
+}
+
+impl GeoJsonParser {
+    /// Builds a `FeatureCollection` holding the flight as a single `LineString` feature, with the
+    /// position times in its `coordTimes` property so the output can be read back in.
+    pub fn to_geojson(&self, geodata: &FlightGeodata) -> Value {
+        let coordinates = geodata
+            .positions()
+            .iter()
+            .map(|p| json!([p.longitude, p.latitude, p.altitude]))
+            .collect::<Vec<_>>();
+        let times = geodata
+            .positions()
+            .iter()
+            .map(|p| p.timestamp.to_rfc3339_opts(SecondsFormat::AutoSi, true))
+            .collect::<Vec<_>>();
+
+        json!({
+            "type": "FeatureCollection",
+            "features": [{
+                "type": "Feature",
+                "properties": {
+                    "flight_code": geodata.flight_code,
+                    "coordTimes": times,
+                },
+                "geometry": {
+                    "type": "LineString",
+                    "coordinates": coordinates,
+                },
+            }],
+        })
+    }
+
+    fn get_flight_code(&self, src: &Value) -> Option<String> {
+        let properties = src.get("properties").unwrap_or(src);
+
+        ["flight_code", "flight", "callsign", "name"]
+            .iter()
+            .find_map(|key| properties.get(key)?.as_str())
+            .filter(|code| !code.is_empty())
+            .map(str::to_string)
+    }
+
+    fn get_feature_positions(&self, feature: &Value) -> GTResult<Vec<GeoPosition>> {
+        let Some(geometry) = feature.get("geometry").filter(|g| !g.is_null()) else {
+            return Ok(Vec::new());
+        };
+
+        self.get_geometry_positions(geometry, feature.get("properties"))
+    }
+
+    fn get_geometry_positions(
+        &self,
+        geometry: &Value,
+        properties: Option<&Value>,
+    ) -> GTResult<Vec<GeoPosition>> {
+        let coordinates = geometry.get("coordinates");
+
+        match geometry.get("type").and_then(Value::as_str) {
+            Some("Point") => {
+                // Points without a time are landmarks rather than track data.
+                let Some(time) = properties
+                    .and_then(|p| p.get("time").or_else(|| p.get("timestamp")))
+                    .filter(|t| !t.is_null())
+                else {
+                    return Ok(Vec::new());
+                };
+
+                let coordinate = coordinates.ok_or(GTError::MissingData(
+                    "Point geometry has no coordinates.".to_string(),
+                ))?;
+
+                Ok(vec![self.get_geoposition(coordinate, time)?])
+            }
+            Some("LineString" | "MultiPoint") => {
+                let coordinates =
+                    coordinates
+                        .and_then(Value::as_array)
+                        .ok_or(GTError::MissingData(
+                            "Geometry has no coordinates array.".to_string(),
+                        ))?;
+                let times = properties
+                    .and_then(|p| p.get("coordTimes").or_else(|| p.get("times")))
+                    .and_then(Value::as_array)
+                    .ok_or(GTError::MissingData(
+                        "LineString/MultiPoint has no coordTimes property.".to_string(),
+                    ))?;
+
+                if coordinates.len() != times.len() {
+                    return Err(GTError::InvalidData(format!(
+                        "Geometry has {} coordinates but {} coordTimes.",
+                        coordinates.len(),
+                        times.len()
+                    )));
+                }
+
+                coordinates
+                    .iter()
+                    .zip(times)
+                    .map(|(coordinate, time)| self.get_geoposition(coordinate, time))
+                    .collect()
+            }
+            _ => Ok(Vec::new()),
+        }
+    }
+
+    fn get_geoposition(&self, coordinate: &Value, time: &Value) -> GTResult<GeoPosition> {
+        let invalid_coordinate =
+            || GTError::InvalidData(format!("Invalid coordinate {coordinate}."));
+        let position = coordinate.as_array().ok_or_else(invalid_coordinate)?;
+        let axis = |i: usize| position.get(i).and_then(Value::as_f64);
+
+        Ok(GeoPosition {
+            timestamp: self.get_timestamp(time)?,
+            latitude: axis(1).ok_or_else(invalid_coordinate)?,
+            longitude: axis(0).ok_or_else(invalid_coordinate)?,
+            altitude: axis(2).unwrap_or_default().round() as i64,
+            speed: None,
+            heading: None,
+            vertical_speed: None,
+        })
+    }
+
+    fn get_timestamp(&self, time: &Value) -> GTResult<DateTime<Utc>> {
+        // Larger Unix timestamps are taken to be in milliseconds (in seconds, this is ~year 5138).
+        const MAX_SECONDS: i64 = 100_000_000_000;
+
+        let timestamp = match time {
+            Value::String(s) => DateTime::parse_from_rfc3339(s)
+                .ok()
+                .map(|dt| dt.with_timezone(&Utc)),
+            Value::Number(n) => n.as_i64().and_then(|n| {
+                if n.abs() < MAX_SECONDS {
+                    DateTime::from_timestamp(n, 0)
+                } else {
+                    DateTime::from_timestamp_millis(n)
+                }
+            }),
+            _ => None,
+        };
+
+        timestamp.ok_or(GTError::InvalidData(format!("Invalid time value {time}.")))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::GeoJsonParser;
+    use crate::parsers::json_parser::JsonParser;
+
+    #[test]
+    fn parses_a_feature_collection_of_points() {
+        let src = json!({
+            "type": "FeatureCollection",
+            "features": [
+                {
+                    "type": "Feature",
+                    "properties": { "time": "2024-01-01T10:01:00Z", "callsign": "DLH4U" },
+                    "geometry": { "type": "Point", "coordinates": [8.57, 50.04, 1200.4] },
+                },
+                {
+                    "type": "Feature",
+                    "properties": { "name": "Airport" },
+                    "geometry": { "type": "Point", "coordinates": [8.56, 50.03] },
+                },
+                {
+                    "type": "Feature",
+                    "properties": { "timestamp": 1704103200000_i64 },
+                    "geometry": { "type": "Point", "coordinates": [8.55, 50.03] },
+                },
+            ],
+        });
+
+        let data = GeoJsonParser {}
+            .try_parse_geodata(src)
+            .unwrap_or_else(|e| panic!("{e}"));
+        let positions = data.positions();
+
+        assert_eq!(data.flight_code, "DLH4U");
+        assert_eq!(positions.len(), 2);
+        assert_eq!(positions[0].timestamp.timestamp(), 1704103200);
+        assert_eq!(positions[1].latitude, 50.04);
+        assert_eq!(positions[1].altitude, 1200);
+    }
+
+    #[test]
+    fn mismatched_coord_times_are_an_error() {
+        let src = json!({
+            "type": "Feature",
+            "properties": { "coordTimes": ["2024-01-01T10:00:00Z"] },
+            "geometry": { "type": "LineString", "coordinates": [[8.55, 50.03], [8.57, 50.04]] },
+        });
+
+        assert!(GeoJsonParser {}.try_parse_geodata(src).is_err());
+    }
+
+    #[test]
+    fn exported_geojson_reads_back_in() {
+        let src = json!({
+            "type": "Feature",
+            "properties": {
+                "flight_code": "BA123",
+                "coordTimes": ["2024-01-01T10:00:00Z", "2024-01-01T10:00:30.5Z"],
+            },
+            "geometry": {
+                "type": "LineString",
+                "coordinates": [[-0.45, 51.47, 25], [-0.5, 51.48, 640]],
+            },
+        });
+        let parser = GeoJsonParser {};
+        let original = parser
+            .try_parse_geodata(src)
+            .unwrap_or_else(|e| panic!("{e}"));
+
+        let round_trip = parser
+            .try_parse_geodata(parser.to_geojson(&original))
+            .unwrap_or_else(|e| panic!("{e}"));
+
+        assert_eq!(round_trip.flight_code, original.flight_code);
+        assert_eq!(round_trip.positions().len(), original.positions().len());
+        for (a, b) in round_trip.positions().iter().zip(original.positions()) {
+            assert_eq!(a.timestamp, b.timestamp);
+            assert_eq!(a.latitude, b.latitude);
+            assert_eq!(a.longitude, b.longitude);
+            assert_eq!(a.altitude, b.altitude);
+        }
+    }
+}
@@ -0,0 +1,188 @@
+use chrono::{DateTime, Utc};
+use roxmltree::{Document, Node};
+
+use crate::models::{
+    flight_geodata::{FlightGeodata, GeoPosition},
+    result::{GTError, GTResult},
+};
+
+use super::track_parser::{fill_altitudes, xml_root_name, TrackParser, TrackSource};
+
+/// Parses flight geodata from the `<trkpt>` elements of a GPX 1.0/1.1 document.
+pub struct GpxParser {}
+
+impl GpxParser {
+    pub fn try_parse_geodata(&self, src: &str) -> GTResult<FlightGeodata> {
+        let doc = Document::parse(src)?;
+        let root = doc.root_element();
+
+        if root.tag_name().name() != "gpx" {
+            return Err(GTError::InvalidData(format!(
+                "Expected <gpx> root element, found <{}>.",
+                root.tag_name().name()
+            )));
+        }
+
+        let flight_code = self.get_flight_code(root).unwrap_or_default();
+        let mut positions = Vec::new();
+        let mut elevations = Vec::new();
+        for trkpt in root.descendants().filter(|n| n.has_tag_name("trkpt")) {
+            let (position, elevation) = self.get_geoposition(trkpt)?;
+            positions.push(position);
+            elevations.push(elevation);
+        }
+
+        if positions.is_empty() {
+            return Err(GTError::MissingData(
+                "No track points found in GPX document.".to_string(),
+            ));
+        }
+
+        // <ele> is optional, so points without one hold the nearest known altitude.
+        fill_altitudes(&mut positions, &elevations);
+
+        FlightGeodata::new(flight_code, positions)
+    }
+
+    fn get_flight_code(&self, root: Node) -> Option<String> {
+        let trk_name = root
+            .children()
+            .find(|n| n.has_tag_name("trk"))
+            .and_then(|trk| child_text(trk, "name"));
+        let metadata_name = root
+            .children()
+            .find(|n| n.has_tag_name("metadata"))
+            .and_then(|metadata| child_text(metadata, "name"));
+
+        // GPX 1.0 has no <metadata> element and puts <name> directly under <gpx>.
+        trk_name
+            .or(metadata_name)
+            .or_else(|| child_text(root, "name"))
+            .map(|name| name.trim().to_string())
+    }
+
+    /// Reads a track point, along with its elevation if it has one. The altitude of the returned
+    /// position is left for the caller to fill in.
+    fn get_geoposition(&self, trkpt: Node) -> GTResult<(GeoPosition, Option<i64>)> {
+        let pos = trkpt.document().text_pos_at(trkpt.range().start);
+
+        let latitude = trkpt
+            .attribute("lat")
+            .and_then(|v| v.trim().parse::<f64>().ok())
+            .ok_or(GTError::InvalidData(format!(
+                "Track point at {pos} has a missing or invalid 'lat' attribute."
+            )))?;
+        let longitude = trkpt
+            .attribute("lon")
+            .and_then(|v| v.trim().parse::<f64>().ok())
+            .ok_or(GTError::InvalidData(format!(
+                "Track point at {pos} has a missing or invalid 'lon' attribute."
+            )))?;
+        let timestamp = child_text(trkpt, "time")
+            .and_then(|v| DateTime::parse_from_rfc3339(v.trim()).ok())
+            .ok_or(GTError::InvalidData(format!(
+                "Track point at {pos} has a missing or invalid <time> element."
+            )))?;
+        let elevation = child_text(trkpt, "ele")
+            .map(|v| {
+                v.trim().parse::<f64>().map_err(|_| {
+                    GTError::InvalidData(format!(
+                        "Track point at {pos} has an invalid <ele> element."
+                    ))
+                })
+            })
+            .transpose()?;
+
+        let position = GeoPosition {
+            timestamp: timestamp.with_timezone(&Utc),
+            latitude,
+            longitude,
+            altitude: 0,
+            speed: None,
+            heading: None,
+            vertical_speed: None,
+        };
+
+        Ok((position, elevation.map(|e| e.round() as i64)))
+    }
+}
+
+impl TrackParser for GpxParser {
+    fn format_name(&self) -> &'static str {
+        "GPX"
+    }
+
+    fn sniff(&self, src: &TrackSource) -> bool {
+        xml_root_name(src.bytes()) == Some("gpx")
+    }
+
+    fn parse_track(&self, src: TrackSource) -> GTResult<FlightGeodata> {
+        let src = std::str::from_utf8(src.bytes())
+            .map_err(|e| GTError::InvalidData(format!("GPX file is not valid UTF-8: {e}")))?;
+
+        self.try_parse_geodata(src)
+    }
+}
+
+fn child_text<'a>(node: Node<'a, '_>, name: &str) -> Option<&'a str> {
+    node.children().find(|n| n.has_tag_name(name))?.text()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::GpxParser;
+    use crate::parsers::track_parser::{TrackParser, TrackSource};
+
+    const GPX: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<gpx version="1.1" creator="test" xmlns="http://www.topografix.com/GPX/1/1">
+  <trk>
+    <name>BA123</name>
+    <trkseg>
+      <trkpt lat="51.4700" lon="-0.4543"><time>2024-01-01T10:00:30Z</time></trkpt>
+      <trkpt lat="51.4710" lon="-0.4600"><ele>85.4</ele><time>2024-01-01T10:00:00Z</time></trkpt>
+      <trkpt lat="51.4800" lon="-0.5000"><time>2024-01-01T10:01:00Z</time></trkpt>
+      <trkpt lat="51.4900" lon="-0.5500"><ele>640</ele><time>2024-01-01T10:01:30Z</time></trkpt>
+    </trkseg>
+  </trk>
+</gpx>"#;
+
+    #[test]
+    fn parses_track_points_in_time_order() {
+        let data = GpxParser {}
+            .try_parse_geodata(GPX)
+            .unwrap_or_else(|e| panic!("{e}"));
+        let positions = data.positions();
+
+        assert_eq!(data.flight_code, "BA123");
+        assert_eq!(positions.len(), 4);
+        assert_eq!(positions[0].latitude, 51.4710);
+        assert_eq!(positions[3].longitude, -0.55);
+    }
+
+    #[test]
+    fn missing_elevations_hold_the_nearest_known_one() {
+        let data = GpxParser {}
+            .try_parse_geodata(GPX)
+            .unwrap_or_else(|e| panic!("{e}"));
+        let altitudes = data
+            .positions()
+            .iter()
+            .map(|p| p.altitude)
+            .collect::<Vec<_>>();
+
+        assert_eq!(altitudes, [85, 85, 85, 640]);
+    }
+
+    #[test]
+    fn invalid_elevation_is_an_error() {
+        let src = GPX.replace("<ele>640</ele>", "<ele>high</ele>");
+
+        assert!(GpxParser {}.try_parse_geodata(&src).is_err());
+    }
+
+    #[test]
+    fn sniffs_gpx_documents() {
+        assert!(GpxParser {}.sniff(&TrackSource::new(GPX.as_bytes())));
+        assert!(!GpxParser {}.sniff(&TrackSource::new(b"<kml></kml>")));
+    }
+}
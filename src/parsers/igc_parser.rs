@@ -0,0 +1,208 @@
+use chrono::{Days, NaiveDate, NaiveTime};
+
+use crate::models::{
+    flight_geodata::{FlightGeodata, GeoPosition},
+    result::{GTError, GTResult},
+};
+
+use super::track_parser::{first_line, TrackParser, TrackSource};
+
+/// Parses flight geodata from the B (fix) records of an IGC flight recorder file.
+pub struct IgcParser {}
+
+impl IgcParser {
+    pub fn try_parse_geodata(&self, src: &str) -> GTResult<FlightGeodata> {
+        let mut date = None;
+        let mut flight_code = String::new();
+        let mut previous_time = None;
+        let mut positions = Vec::new();
+
+        for (i, line) in src.lines().enumerate() {
+            let line = line.trim_end();
+            let line_number = i + 1;
+
+            if let Some(header) = line.strip_prefix("HFDTE") {
+                date = Some(self.parse_date(header, line_number)?);
+            } else if let Some(header) = line.strip_prefix("HFGID") {
+                flight_code = Self::header_value(header).to_string();
+            } else if line.starts_with('B') {
+                let date = date.as_mut().ok_or(GTError::MissingData(
+                    "IGC file has a B record before the HFDTE date header.".to_string(),
+                ))?;
+
+                let (time, mut position) = self.parse_fix(line, line_number)?;
+
+                // B records only carry a time of day, so a flight past midnight UTC wraps around.
+                if previous_time.is_some_and(|previous| time < previous) {
+                    *date = date
+                        .checked_add_days(Days::new(1))
+                        .ok_or(GTError::Conversion("IGC date overflowed.".to_string()))?;
+                }
+                previous_time = Some(time);
+
+                position.timestamp = date.and_time(time).and_utc();
+                positions.push(position);
+            }
+        }
+
+        if positions.is_empty() {
+            return Err(GTError::MissingData(
+                "No B records found in IGC file.".to_string(),
+            ));
+        }
+
+        FlightGeodata::new(flight_code, positions)
+    }
+
+    /// Strips the optional long-form label (e.g. `DATE:` or `GLIDERID:`) from a header value.
+    fn header_value(header: &str) -> &str {
+        header
+            .split_once(':')
+            .map_or(header, |(_, value)| value)
+            .trim()
+    }
+
+    /// Parses `DDMMYY`, optionally followed by a `,NN` flight number.
+    fn parse_date(&self, header: &str, line_number: usize) -> GTResult<NaiveDate> {
+        let value = Self::header_value(header);
+        let value = value.split(',').next().unwrap_or(value);
+
+        NaiveDate::parse_from_str(value, "%d%m%y").map_err(|e| {
+            GTError::InvalidData(format!(
+                "Invalid HFDTE date '{value}' on line {line_number} of IGC file: {e}"
+            ))
+        })
+    }
+
+    /// Parses a `BHHMMSSDDMMmmmNDDDMMmmmEVPPPPPGGGGG` fix record.
+    fn parse_fix(&self, line: &str, line_number: usize) -> GTResult<(NaiveTime, GeoPosition)> {
+        let invalid = |field: &str| {
+            GTError::InvalidData(format!(
+                "Invalid {field} in B record on line {line_number} of IGC file."
+            ))
+        };
+        let field = |range: std::ops::Range<usize>, name: &str| {
+            line.get(range).ok_or_else(|| invalid(name))
+        };
+        let number = |range: std::ops::Range<usize>, name: &str| {
+            field(range, name)?
+                .trim()
+                .parse::<f64>()
+                .map_err(|_| invalid(name))
+        };
+
+        let time = NaiveTime::parse_from_str(field(1..7, "time")?, "%H%M%S")
+            .map_err(|_| invalid("time"))?;
+
+        let latitude = number(7..9, "latitude")? + number(9..14, "latitude")? / 60_000.0;
+        let latitude = match field(14..15, "latitude")? {
+            "N" => latitude,
+            "S" => -latitude,
+            _ => return Err(invalid("latitude")),
+        };
+
+        let longitude = number(15..18, "longitude")? + number(18..23, "longitude")? / 60_000.0;
+        let longitude = match field(23..24, "longitude")? {
+            "E" => longitude,
+            "W" => -longitude,
+            _ => return Err(invalid("longitude")),
+        };
+
+        // Prefer the GNSS altitude, falling back to pressure altitude when the recorder has no
+        // GNSS altitude (written as zero).
+        let pressure_altitude = number(25..30, "pressure altitude")?;
+        let gnss_altitude = number(30..35, "GNSS altitude")?;
+        let altitude = if gnss_altitude != 0.0 {
+            gnss_altitude
+        } else {
+            pressure_altitude
+        };
+
+        let position = GeoPosition {
+            timestamp: Default::default(),
+            latitude,
+            longitude,
+            altitude: altitude as i64,
+            speed: None,
+            heading: None,
+            vertical_speed: None,
+        };
+
+        Ok((time, position))
+    }
+}
+
+impl TrackParser for IgcParser {
+    fn format_name(&self) -> &'static str {
+        "IGC"
+    }
+
+    fn sniff(&self, src: &TrackSource) -> bool {
+        let src = src.bytes();
+
+        first_line(src).is_some_and(|line| line.starts_with('A'))
+            && src
+                .split(|b| *b == b'\n')
+                .any(|line| line.starts_with(b"HFDTE"))
+    }
+
+    fn parse_track(&self, src: TrackSource) -> GTResult<FlightGeodata> {
+        // Header records are free text and are not guaranteed to be UTF-8.
+        self.try_parse_geodata(&String::from_utf8_lossy(src.bytes()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeZone, Utc};
+
+    use super::IgcParser;
+
+    const IGC: &str = "AXXXABC Flight Recorder
+HFDTEDATE:311223,01
+HFGIDGLIDERID:D-KXYZ
+B2359305128250N00029170WA0012000150
+B0000305129000S00030000EA0020000000
+";
+
+    #[test]
+    fn parses_fixes_and_rolls_over_midnight() {
+        let data = IgcParser {}
+            .try_parse_geodata(IGC)
+            .unwrap_or_else(|e| panic!("{e}"));
+        let positions = data.positions();
+
+        assert_eq!(data.flight_code, "D-KXYZ");
+        assert_eq!(
+            positions[0].timestamp,
+            Utc.with_ymd_and_hms(2023, 12, 31, 23, 59, 30).unwrap()
+        );
+        assert_eq!(
+            positions[1].timestamp,
+            Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 30).unwrap()
+        );
+    }
+
+    #[test]
+    fn reads_hemispheres_and_altitudes() {
+        let data = IgcParser {}
+            .try_parse_geodata(IGC)
+            .unwrap_or_else(|e| panic!("{e}"));
+        let positions = data.positions();
+
+        assert!((positions[0].latitude - (51.0 + 28.25 / 60.0)).abs() < 1e-9);
+        assert!((positions[0].longitude + 29.17 / 60.0).abs() < 1e-9);
+        assert_eq!(positions[0].altitude, 150);
+        assert!((positions[1].latitude + (51.0 + 29.0 / 60.0)).abs() < 1e-9);
+        assert!((positions[1].longitude - 0.5).abs() < 1e-9);
+        // Without a GNSS altitude, the pressure altitude is used.
+        assert_eq!(positions[1].altitude, 200);
+    }
+
+    #[test]
+    fn fix_before_the_date_header_is_an_error() {
+        let src = "AXXX\nB2359305128250N00029170WA0012000150\nHFDTE311223\n";
+
+        assert!(IgcParser {}.try_parse_geodata(src).is_err());
+    }
+}
@@ -1,10 +1,13 @@
 use chrono::DateTime;
 
 use crate::models::{
-    flight_geodata::{FlightGeodata, GeoPosition},
+    coord::Converter,
+    flight_geodata::{Airport, FlightGeodata, GeoPosition},
     result::{GTError, GTResult},
 };
 
+use super::track_parser::{TrackParser, TrackSource};
+
 pub trait JsonParser {
     fn try_parse_geodata(&self, src: serde_json::Value) -> GTResult<FlightGeodata>;
 }
@@ -18,11 +21,31 @@ impl JsonParser for FlightRadar24JsonParser {
 
         let positions = self.get_geopositions(&src).ok_or(GTError::Parser)?;
 
-        Ok(FlightGeodata::new(flight_code, positions))
+        Ok(FlightGeodata::new(flight_code, positions)?.with_airports(
+            self.get_airport(&src, "origin"),
+            self.get_airport(&src, "destination"),
+        ))
+    }
+}
+
+impl TrackParser for FlightRadar24JsonParser {
+    fn format_name(&self) -> &'static str {
+        "FlightRadar24 playback JSON"
+    }
+
+    fn sniff(&self, src: &TrackSource) -> bool {
+        src.json()
+            .is_some_and(|v| v.pointer(Self::TRACK_POINTER).is_some())
+    }
+
+    fn parse_track(&self, src: TrackSource) -> GTResult<FlightGeodata> {
+        self.try_parse_geodata(src.into_json()?)
     }
 }
 
 impl FlightRadar24JsonParser {
+    const TRACK_POINTER: &'static str = "/result/response/data/flight/track";
+
     fn get_flight_code(&self, src: &serde_json::Value) -> Option<String> {
         let value = src
             .pointer("/result/response/data/flight/identification/number/default")?
@@ -30,13 +53,30 @@ impl FlightRadar24JsonParser {
         Some(value.to_string())
     }
 
+    /// Reads the `origin` or `destination` airport from the flight metadata.
+    fn get_airport(&self, src: &serde_json::Value, end: &str) -> Option<Airport> {
+        let value = src.pointer(&format!("/result/response/data/flight/airport/{end}"))?;
+
+        Some(Airport {
+            code: value.pointer("/code/iata")?.as_str()?.to_string(),
+            latitude: value.pointer("/position/latitude")?.as_f64()?,
+            longitude: value.pointer("/position/longitude")?.as_f64()?,
+            // FlightRadar24 gives airport elevations in feet.
+            altitude: Converter::feet_to_metres(
+                value
+                    .pointer("/position/altitude")
+                    .and_then(serde_json::Value::as_f64)
+                    .unwrap_or(0.0),
+            )
+            .round() as i64,
+        })
+    }
+
     fn get_geopositions(&self, src: &serde_json::Value) -> Option<Vec<GeoPosition>> {
-        let value = src
-            .pointer("/result/response/data/flight/track")?
-            .as_array()?;
+        let value = src.pointer(Self::TRACK_POINTER)?.as_array()?;
 
         value
-            .into_iter()
+            .iter()
             .map(|v| self.get_geoposition(v))
             .collect::<Option<Vec<GeoPosition>>>()
     }
@@ -47,6 +87,32 @@ impl FlightRadar24JsonParser {
             latitude: src.get("latitude")?.as_f64()?,
             longitude: src.get("longitude")?.as_f64()?,
             altitude: src.pointer("/altitude/meters")?.as_i64()?,
+            speed: src
+                .pointer("/speed/kts")
+                .and_then(serde_json::Value::as_f64),
+            heading: src.get("heading").and_then(serde_json::Value::as_f64),
+            vertical_speed: src
+                .pointer("/verticalSpeed/fpm")
+                .and_then(serde_json::Value::as_f64),
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::{FlightRadar24JsonParser, JsonParser};
+
+    #[test]
+    fn empty_track_is_an_error() {
+        let src = json!({
+            "result": { "response": { "data": { "flight": {
+                "identification": { "number": { "default": "BA123" } },
+                "track": [],
+            } } } }
+        });
+
+        assert!(FlightRadar24JsonParser {}.try_parse_geodata(src).is_err());
+    }
+}
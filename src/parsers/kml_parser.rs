@@ -0,0 +1,344 @@
+use std::io::{Cursor, Read, Seek};
+
+use chrono::{DateTime, Utc};
+use roxmltree::{Document, Node};
+use zip::ZipArchive;
+
+use crate::models::{
+    flight_geodata::{FlightGeodata, GeoPosition},
+    result::{GTError, GTResult},
+};
+
+use super::track_parser::{xml_root_name, TrackParser, TrackSource};
+
+/// Parses flight geodata from a KML document.
+///
+/// Both `gx:Track` elements (paired `<when>`/`<gx:coord>` children) and placemarks carrying a
+/// `<TimeStamp>` alongside a `Point` or `LineString` geometry are read. Placemarks without any
+/// time information (such as the plain trail line in FlightRadar24 exports) are ignored.
+pub struct KmlParser {}
+
+impl KmlParser {
+    pub fn try_parse_geodata(&self, src: &str) -> GTResult<FlightGeodata> {
+        let doc = Document::parse(src)?;
+        let root = doc.root_element();
+
+        if root.tag_name().name() != "kml" {
+            return Err(GTError::InvalidData(format!(
+                "Expected <kml> root element, found <{}>.",
+                root.tag_name().name()
+            )));
+        }
+
+        let flight_code = self.get_flight_code(root).unwrap_or_default();
+
+        let mut positions = Vec::new();
+        for node in root.descendants() {
+            if node.has_tag_name("Track") {
+                positions.extend(self.get_track_positions(node)?);
+            } else if node.has_tag_name("Placemark") {
+                positions.extend(self.get_placemark_position(node)?);
+            }
+        }
+
+        if positions.is_empty() {
+            return Err(GTError::MissingData(
+                "No timestamped track data found in KML document.".to_string(),
+            ));
+        }
+
+        FlightGeodata::new(flight_code, positions)
+    }
+
+    /// Extracts the main KML document from a KMZ archive. By convention this is `doc.kml`, but
+    /// any `.kml` entry is accepted.
+    pub fn extract_kmz_document<R: Read + Seek>(&self, src: R) -> GTResult<String> {
+        let mut archive = ZipArchive::new(src)?;
+
+        let entry_name = archive
+            .file_names()
+            .filter(|name| name.to_ascii_lowercase().ends_with(".kml"))
+            .min_by_key(|name| (*name != "doc.kml", name.contains('/')))
+            .map(str::to_string)
+            .ok_or(GTError::MissingData(
+                "No .kml document found inside KMZ archive.".to_string(),
+            ))?;
+
+        let mut contents = String::new();
+        archive
+            .by_name(&entry_name)?
+            .read_to_string(&mut contents)?;
+
+        Ok(contents)
+    }
+
+    fn get_flight_code(&self, root: Node) -> Option<String> {
+        let document = root
+            .children()
+            .find(|n| n.has_tag_name("Document") || n.has_tag_name("Folder"))?;
+
+        let name = child_text(document, "name")?.trim();
+        (!name.is_empty()).then(|| name.to_string())
+    }
+
+    fn get_track_positions(&self, track: Node) -> GTResult<Vec<GeoPosition>> {
+        let whens = track
+            .children()
+            .filter(|n| n.has_tag_name("when"))
+            .collect::<Vec<_>>();
+        let coords = track
+            .children()
+            .filter(|n| n.has_tag_name("coord"))
+            .collect::<Vec<_>>();
+
+        if whens.len() != coords.len() {
+            return Err(GTError::InvalidData(format!(
+                "gx:Track has {} <when> elements but {} <gx:coord> elements.",
+                whens.len(),
+                coords.len()
+            )));
+        }
+
+        whens
+            .into_iter()
+            .zip(coords)
+            .map(|(when, coord)| {
+                let timestamp = parse_when(when)?;
+                // gx:coord values are whitespace-separated, unlike <coordinates>.
+                let (longitude, latitude, altitude) =
+                    parse_coordinate(coord, coord.text().unwrap_or_default(), ' ')?;
+
+                Ok(GeoPosition {
+                    timestamp,
+                    latitude,
+                    longitude,
+                    altitude,
+                    speed: None,
+                    heading: None,
+                    vertical_speed: None,
+                })
+            })
+            .collect()
+    }
+
+    fn get_placemark_position(&self, placemark: Node) -> GTResult<Option<GeoPosition>> {
+        let Some(when) = placemark
+            .children()
+            .find(|n| n.has_tag_name("TimeStamp"))
+            .and_then(|ts| ts.children().find(|n| n.has_tag_name("when")))
+        else {
+            return Ok(None);
+        };
+
+        let Some(coordinates) = placemark
+            .descendants()
+            .find(|n| n.has_tag_name("Point") || n.has_tag_name("LineString"))
+            .and_then(|geometry| geometry.children().find(|n| n.has_tag_name("coordinates")))
+        else {
+            return Ok(None);
+        };
+
+        // A timestamped LineString is a segment ending at the stamped time, so only its final
+        // coordinate is known to belong to that instant.
+        let Some(coordinate) = coordinates
+            .text()
+            .unwrap_or_default()
+            .split_whitespace()
+            .last()
+        else {
+            return Ok(None);
+        };
+
+        let timestamp = parse_when(when)?;
+        let (longitude, latitude, altitude) = parse_coordinate(coordinates, coordinate, ',')?;
+
+        Ok(Some(GeoPosition {
+            timestamp,
+            latitude,
+            longitude,
+            altitude,
+            speed: None,
+            heading: None,
+            vertical_speed: None,
+        }))
+    }
+}
+
+impl TrackParser for KmlParser {
+    fn format_name(&self) -> &'static str {
+        "KML/KMZ"
+    }
+
+    fn sniff(&self, src: &TrackSource) -> bool {
+        let src = src.bytes();
+
+        src.starts_with(Self::ZIP_MAGIC) || xml_root_name(src) == Some("kml")
+    }
+
+    fn parse_track(&self, src: TrackSource) -> GTResult<FlightGeodata> {
+        let src = src.bytes();
+
+        if src.starts_with(Self::ZIP_MAGIC) {
+            let contents = self.extract_kmz_document(Cursor::new(src))?;
+            return self.try_parse_geodata(&contents);
+        }
+
+        let src = std::str::from_utf8(src)
+            .map_err(|e| GTError::InvalidData(format!("KML file is not valid UTF-8: {e}")))?;
+
+        self.try_parse_geodata(src)
+    }
+}
+
+impl KmlParser {
+    const ZIP_MAGIC: &'static [u8] = b"PK\x03\x04";
+}
+
+fn child_text<'a>(node: Node<'a, '_>, name: &str) -> Option<&'a str> {
+    node.children().find(|n| n.has_tag_name(name))?.text()
+}
+
+fn parse_when(when: Node) -> GTResult<DateTime<Utc>> {
+    let text = when.text().unwrap_or_default().trim();
+
+    DateTime::parse_from_rfc3339(text)
+        .map(|dt| dt.with_timezone(&Utc))
+        .map_err(|e| {
+            GTError::InvalidData(format!(
+                "Invalid <when> value '{text}' at {}: {e}",
+                when.document().text_pos_at(when.range().start)
+            ))
+        })
+}
+
+/// Parses a `lon<sep>lat[<sep>alt]` tuple. A missing altitude is taken as ground level.
+fn parse_coordinate(node: Node, text: &str, separator: char) -> GTResult<(f64, f64, i64)> {
+    let invalid = || {
+        GTError::InvalidData(format!(
+            "Invalid coordinate '{}' at {}.",
+            text.trim(),
+            node.document().text_pos_at(node.range().start)
+        ))
+    };
+
+    let mut parts = text
+        .split(separator)
+        .map(str::trim)
+        .filter(|p| !p.is_empty());
+    let mut next_f64 = || {
+        parts
+            .next()
+            .map(|p| p.parse::<f64>().map_err(|_| invalid()))
+    };
+
+    let longitude = next_f64().ok_or_else(invalid)??;
+    let latitude = next_f64().ok_or_else(invalid)??;
+    let altitude = next_f64().transpose()?.unwrap_or(0.0);
+
+    Ok((longitude, latitude, altitude.round() as i64))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Cursor, Write};
+
+    use zip::{write::SimpleFileOptions, ZipWriter};
+
+    use super::KmlParser;
+    use crate::parsers::track_parser::{TrackParser, TrackSource};
+
+    const GX_TRACK: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<kml xmlns="http://www.opengis.net/kml/2.2" xmlns:gx="http://www.google.com/kml/ext/2.2">
+  <Document>
+    <name>EZY8421</name>
+    <Placemark>
+      <name>Trail</name>
+      <LineString><coordinates>-0.4543,51.47 -0.5,51.48</coordinates></LineString>
+    </Placemark>
+    <Placemark>
+      <gx:Track>
+        <when>2024-01-01T10:00:00Z</when>
+        <when>2024-01-01T10:01:00Z</when>
+        <gx:coord>-0.4543 51.47 25</gx:coord>
+        <gx:coord>-0.5 51.48 640</gx:coord>
+      </gx:Track>
+    </Placemark>
+  </Document>
+</kml>"#;
+
+    const TIMESTAMPED_PLACEMARKS: &str = r#"<kml xmlns="http://www.opengis.net/kml/2.2">
+  <Folder>
+    <Placemark>
+      <TimeStamp><when>2024-01-01T10:00:00Z</when></TimeStamp>
+      <Point><coordinates>14.49,35.85,100</coordinates></Point>
+    </Placemark>
+    <Placemark>
+      <TimeStamp><when>2024-01-01T10:01:00+01:00</when></TimeStamp>
+      <LineString><coordinates>14.49,35.85,100 14.6,35.9</coordinates></LineString>
+    </Placemark>
+  </Folder>
+</kml>"#;
+
+    fn kmz(entries: &[(&str, &str)]) -> Vec<u8> {
+        let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+        for (name, contents) in entries {
+            writer
+                .start_file(*name, SimpleFileOptions::default())
+                .unwrap();
+            writer.write_all(contents.as_bytes()).unwrap();
+        }
+
+        writer.finish().unwrap().into_inner()
+    }
+
+    #[test]
+    fn parses_gx_tracks_and_ignores_untimed_placemarks() {
+        let data = KmlParser {}
+            .try_parse_geodata(GX_TRACK)
+            .unwrap_or_else(|e| panic!("{e}"));
+        let positions = data.positions();
+
+        assert_eq!(data.flight_code, "EZY8421");
+        assert_eq!(positions.len(), 2);
+        assert_eq!(positions[1].latitude, 51.48);
+        assert_eq!(positions[1].longitude, -0.5);
+        assert_eq!(positions[1].altitude, 640);
+    }
+
+    #[test]
+    fn timestamped_line_strings_use_their_last_coordinate() {
+        let data = KmlParser {}
+            .try_parse_geodata(TIMESTAMPED_PLACEMARKS)
+            .unwrap_or_else(|e| panic!("{e}"));
+        let positions = data.positions();
+
+        // 10:01+01:00 is 09:01 UTC, so it sorts first.
+        assert_eq!(positions[0].longitude, 14.6);
+        assert_eq!(positions[0].altitude, 0);
+        assert_eq!(positions[1].altitude, 100);
+    }
+
+    #[test]
+    fn extracts_doc_kml_from_kmz() {
+        let src = kmz(&[
+            ("files/other.kml", "<kml/>"),
+            ("doc.kml", GX_TRACK),
+            ("files/icon.png", ""),
+        ]);
+        let source = TrackSource::new(&src);
+
+        assert!(KmlParser {}.sniff(&source));
+
+        let data = KmlParser {}
+            .parse_track(source)
+            .unwrap_or_else(|e| panic!("{e}"));
+        assert_eq!(data.positions().len(), 2);
+    }
+
+    #[test]
+    fn kmz_without_a_document_is_an_error() {
+        let src = kmz(&[("files/icon.png", "")]);
+
+        assert!(KmlParser {}.parse_track(TrackSource::new(&src)).is_err());
+    }
+}
@@ -0,0 +1,144 @@
+use chrono::DateTime;
+use serde_json::Value;
+
+use crate::models::{
+    flight_geodata::{FlightGeodata, GeoPosition},
+    result::{GTError, GTResult},
+};
+
+use super::{
+    json_parser::JsonParser,
+    track_parser::{fill_altitudes, TrackParser, TrackSource},
+};
+
+/// Parses flight geodata from an OpenSky Network `tracks/all` response.
+///
+/// Each `path` waypoint is `[time, lat, lon, baro_altitude, true_track, on_ground]`, with the
+/// altitude in metres.
+pub struct OpenSkyJsonParser {}
+
+impl JsonParser for OpenSkyJsonParser {
+    fn try_parse_geodata(&self, src: Value) -> GTResult<FlightGeodata> {
+        let path = src
+            .get("path")
+            .and_then(Value::as_array)
+            .ok_or(GTError::MissingData("Track has no path array.".to_string()))?;
+
+        let mut altitudes = Vec::with_capacity(path.len());
+        let mut positions = Vec::with_capacity(path.len());
+        for (i, waypoint) in path.iter().enumerate() {
+            let waypoint = waypoint.as_array().ok_or(GTError::InvalidData(format!(
+                "Track waypoint {i} is not an array."
+            )))?;
+            let invalid = |name: &str| {
+                GTError::InvalidData(format!(
+                    "Track waypoint {i} has a missing or invalid {name}."
+                ))
+            };
+
+            let timestamp = waypoint
+                .first()
+                .and_then(Value::as_i64)
+                .and_then(|t| DateTime::from_timestamp(t, 0))
+                .ok_or_else(|| invalid("time"))?;
+            let latitude = waypoint
+                .get(1)
+                .and_then(Value::as_f64)
+                .ok_or_else(|| invalid("latitude"))?;
+            let longitude = waypoint
+                .get(2)
+                .and_then(Value::as_f64)
+                .ok_or_else(|| invalid("longitude"))?;
+
+            // The barometric altitude is null when unknown. The airport elevation isn't given, so
+            // ground reports are taken as 0 m whatever altitude they carry.
+            let on_ground = waypoint.get(5).and_then(Value::as_bool).unwrap_or(false);
+            altitudes.push(if on_ground {
+                Some(0)
+            } else {
+                waypoint
+                    .get(3)
+                    .and_then(Value::as_f64)
+                    .map(|altitude| altitude.round() as i64)
+            });
+
+            positions.push(GeoPosition {
+                timestamp,
+                latitude,
+                longitude,
+                altitude: 0,
+                speed: None,
+                heading: waypoint.get(4).and_then(Value::as_f64),
+                vertical_speed: None,
+            });
+        }
+
+        if positions.is_empty() {
+            return Err(GTError::MissingData("Track has no waypoints.".to_string()));
+        }
+
+        fill_altitudes(&mut positions, &altitudes);
+
+        let flight_code = src
+            .get("callsign")
+            .and_then(Value::as_str)
+            .map(str::trim)
+            .filter(|c| !c.is_empty())
+            .or_else(|| src.get("icao24")?.as_str())
+            .unwrap_or_default()
+            .to_string();
+
+        FlightGeodata::new(flight_code, positions)
+    }
+}
+
+impl TrackParser for OpenSkyJsonParser {
+    fn format_name(&self) -> &'static str {
+        "OpenSky track JSON"
+    }
+
+    fn sniff(&self, src: &TrackSource) -> bool {
+        src.json().is_some_and(|v| {
+            v.get("icao24").is_some() && v.get("path").is_some_and(Value::is_array)
+        })
+    }
+
+    fn parse_track(&self, src: TrackSource) -> GTResult<FlightGeodata> {
+        self.try_parse_geodata(src.into_json()?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::OpenSkyJsonParser;
+    use crate::parsers::{
+        json_parser::JsonParser,
+        track_parser::{TrackParser, TrackSource},
+    };
+
+    const FIXTURE: &str = include_str!("../../tests/fixtures/opensky_track.json");
+
+    #[test]
+    fn parses_the_track_fixture() {
+        let data = OpenSkyJsonParser {}
+            .try_parse_geodata(serde_json::from_str(FIXTURE).unwrap())
+            .unwrap_or_else(|e| panic!("{e}"));
+
+        assert_eq!(data.flight_code, "RYR12AB");
+
+        let positions = data.positions();
+        assert_eq!(positions.len(), 4);
+        assert_eq!(positions[1].latitude, 53.4301);
+        assert_eq!(positions[1].heading, Some(281.5));
+        // Ground reports are 0 m, and null altitudes hold the last known one.
+        assert_eq!(positions[0].altitude, 0);
+        assert_eq!(positions[2].altitude, 450);
+        assert_eq!(positions[3].altitude, 3200);
+    }
+
+    #[test]
+    fn sniffs_opensky_tracks_only() {
+        assert!(OpenSkyJsonParser {}.sniff(&TrackSource::new(FIXTURE.as_bytes())));
+        assert!(!OpenSkyJsonParser {}.sniff(&TrackSource::new(br#"{"path": []}"#)));
+    }
+}
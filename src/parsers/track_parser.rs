@@ -0,0 +1,280 @@
+use std::cell::OnceCell;
+
+use serde_json::Value;
+
+use crate::models::{
+    flight_geodata::{FlightGeodata, GeoPosition},
+    result::{GTError, GTResult},
+};
+
+use super::{
+    adsbx_parser::AdsbExchangeTraceParser, csv_parser::FlightRadar24CsvParser,
+    geojson_parser::GeoJsonParser, gpx_parser::GpxParser, igc_parser::IgcParser,
+    json_parser::FlightRadar24JsonParser, kml_parser::KmlParser, opensky_parser::OpenSkyJsonParser,
+};
+
+/// A track file format which can be recognised from its raw contents.
+pub trait TrackParser {
+    /// Human-readable name of the format, used when reporting which format was detected.
+    fn format_name(&self) -> &'static str;
+
+    /// Returns whether `src` looks like this format. This must not fail.
+    fn sniff(&self, src: &TrackSource) -> bool;
+
+    fn parse_track(&self, src: TrackSource) -> GTResult<FlightGeodata>;
+}
+
+/// The raw contents of a track file. Several formats are JSON, so it is parsed as JSON at most
+/// once and shared between their sniffers and parser.
+pub struct TrackSource<'a> {
+    bytes: &'a [u8],
+    json: OnceCell<Option<Value>>,
+}
+
+impl<'a> TrackSource<'a> {
+    pub fn new(bytes: &'a [u8]) -> Self {
+        Self {
+            bytes,
+            json: OnceCell::new(),
+        }
+    }
+
+    pub fn bytes(&self) -> &'a [u8] {
+        self.bytes
+    }
+
+    /// The contents as a JSON object, or `None` if they aren't one.
+    pub fn json(&self) -> Option<&Value> {
+        self.json
+            .get_or_init(|| {
+                trim_start(self.bytes)
+                    .starts_with(b"{")
+                    .then(|| serde_json::from_slice(self.bytes).ok())
+                    .flatten()
+            })
+            .as_ref()
+    }
+
+    /// Takes the parsed JSON, parsing it now if no sniffer has.
+    pub fn into_json(self) -> GTResult<Value> {
+        match self.json.into_inner() {
+            Some(Some(value)) => Ok(value),
+            _ => Ok(serde_json::from_slice(self.bytes)?),
+        }
+    }
+}
+
+/// The set of track formats which can be auto-detected, checked in registration order.
+pub struct ParserRegistry {
+    parsers: Vec<Box<dyn TrackParser>>,
+}
+
+impl Default for ParserRegistry {
+    fn default() -> Self {
+        let mut registry = Self::new();
+        registry.register(Box::new(FlightRadar24JsonParser {}));
+        registry.register(Box::new(GeoJsonParser {}));
+        registry.register(Box::new(AdsbExchangeTraceParser {}));
+        registry.register(Box::new(OpenSkyJsonParser {}));
+        registry.register(Box::new(GpxParser {}));
+        registry.register(Box::new(KmlParser {}));
+        registry.register(Box::new(FlightRadar24CsvParser {}));
+        registry.register(Box::new(IgcParser {}));
+
+        registry
+    }
+}
+
+impl ParserRegistry {
+    pub fn new() -> Self {
+        Self {
+            parsers: Vec::new(),
+        }
+    }
+
+    pub fn register(&mut self, parser: Box<dyn TrackParser>) {
+        self.parsers.push(parser);
+    }
+
+    pub fn detect(&self, src: &TrackSource) -> GTResult<&dyn TrackParser> {
+        self.parsers
+            .iter()
+            .find(|p| p.sniff(src))
+            .map(|p| p.as_ref())
+            .ok_or_else(|| {
+                let supported = self
+                    .parsers
+                    .iter()
+                    .map(|p| p.format_name())
+                    .collect::<Vec<_>>()
+                    .join(", ");
+
+                GTError::InvalidData(format!(
+                    "Unrecognised track file format. Supported formats: {supported}."
+                ))
+            })
+    }
+}
+
+/// Strips a UTF-8 byte order mark and leading whitespace, which some exporters emit.
+pub fn trim_start(src: &[u8]) -> &[u8] {
+    let src = src.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(src);
+    let start = src
+        .iter()
+        .position(|b| !b.is_ascii_whitespace())
+        .unwrap_or(src.len());
+
+    &src[start..]
+}
+
+/// Returns the local name of the root element of an XML document without fully parsing it.
+pub fn xml_root_name(src: &[u8]) -> Option<&str> {
+    let mut rest = src;
+
+    loop {
+        rest = trim_start(rest).strip_prefix(b"<")?;
+
+        if let Some(comment) = rest.strip_prefix(b"!--") {
+            rest = skip_past(comment, b"-->")?;
+        } else if let Some(instruction) = rest.strip_prefix(b"?") {
+            // The XML declaration and processing instructions.
+            rest = skip_past(instruction, b"?>")?;
+        } else if rest.starts_with(b"!") {
+            rest = skip_declaration(rest)?;
+        } else {
+            let end = rest
+                .iter()
+                .position(|b| b.is_ascii_whitespace() || matches!(b, b'>' | b'/'))?;
+            let name = std::str::from_utf8(&rest[..end]).ok()?;
+
+            return Some(name.rsplit(':').next().unwrap_or(name));
+        }
+    }
+}
+
+/// Returns what follows the first `terminator` in `src`.
+fn skip_past<'a>(src: &'a [u8], terminator: &[u8]) -> Option<&'a [u8]> {
+    let end = src
+        .windows(terminator.len())
+        .position(|w| w == terminator)?;
+
+    Some(&src[end + terminator.len()..])
+}
+
+/// Returns what follows a markup declaration such as a DOCTYPE, which may contain quoted strings
+/// and a bracketed internal subset with `>` inside it.
+fn skip_declaration(src: &[u8]) -> Option<&[u8]> {
+    let mut depth = 0usize;
+    let mut quote = None;
+
+    for (i, b) in src.iter().enumerate() {
+        match (quote, b) {
+            (Some(q), _) if *b == q => quote = None,
+            (Some(_), _) => {}
+            (None, b'"' | b'\'') => quote = Some(*b),
+            (None, b'[') => depth += 1,
+            (None, b']') => depth = depth.saturating_sub(1),
+            (None, b'>') if depth == 0 => return Some(&src[i + 1..]),
+            _ => {}
+        }
+    }
+
+    None
+}
+
+/// Sets the altitude of each position from `altitudes`. Positions without a known altitude hold
+/// the last known one, and any before the first known altitude take that instead.
+pub fn fill_altitudes(positions: &mut [GeoPosition], altitudes: &[Option<i64>]) {
+    let mut altitude = altitudes.iter().flatten().next().copied().unwrap_or(0);
+
+    for (position, known) in positions.iter_mut().zip(altitudes) {
+        altitude = known.unwrap_or(altitude);
+        position.altitude = altitude;
+    }
+}
+
+/// Returns the first line of a text document.
+pub fn first_line(src: &[u8]) -> Option<&str> {
+    let src = trim_start(src);
+    let end = src
+        .iter()
+        .position(|b| matches!(b, b'\r' | b'\n'))
+        .unwrap_or(src.len());
+
+    std::str::from_utf8(&src[..end]).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{xml_root_name, ParserRegistry, TrackSource};
+
+    fn detected(src: &str) -> Option<&'static str> {
+        ParserRegistry::default()
+            .detect(&TrackSource::new(src.as_bytes()))
+            .ok()
+            .map(|parser| parser.format_name())
+    }
+
+    #[test]
+    fn registry_detects_each_format() {
+        let cases = [
+            (
+                r#"{"result": {"response": {"data": {"flight": {"track": []}}}}}"#,
+                "FlightRadar24 playback JSON",
+            ),
+            (
+                r#"{"type": "FeatureCollection", "features": []}"#,
+                "GeoJSON",
+            ),
+            (
+                r#"{"icao": "4ca7b3", "timestamp": 0, "trace": []}"#,
+                "ADS-B Exchange trace JSON",
+            ),
+            (r#"{"icao24": "4ca7b3", "path": []}"#, "OpenSky track JSON"),
+            (r#"<?xml version="1.0"?><gpx version="1.1"/>"#, "GPX"),
+            ("<!-- export --><kml/>", "KML/KMZ"),
+            (
+                "Timestamp,UTC,Callsign,Position,Altitude,Speed,Direction\n",
+                "FlightRadar24 CSV",
+            ),
+            ("AXXX\r\nHFDTE010124\r\n", "IGC"),
+        ];
+
+        for (src, format) in cases {
+            assert_eq!(detected(src), Some(format), "{src}");
+        }
+    }
+
+    #[test]
+    fn registry_checks_formats_in_order() {
+        // Matches both the FlightRadar24 and GeoJSON sniffers, so the first registered wins.
+        let src =
+            r#"{"type": "Feature", "result": {"response": {"data": {"flight": {"track": []}}}}}"#;
+
+        assert_eq!(detected(src), Some("FlightRadar24 playback JSON"));
+        assert_eq!(detected("lat,lon\n1,2\n"), None);
+    }
+
+    #[test]
+    fn root_name_skips_the_prolog() {
+        let src = br#"<?xml version="1.0" encoding="UTF-8"?>
+<!-- exported > by some tool -->
+<!DOCTYPE gpx [
+  <!ENTITY note "a > b">
+]>
+<?xml-stylesheet href="style.xsl"?>
+<gpx:gpx xmlns:gpx="http://www.topografix.com/GPX/1/1"></gpx:gpx>"#;
+
+        assert_eq!(xml_root_name(src), Some("gpx"));
+        assert_eq!(xml_root_name(b"\xEF\xBB\xBF  <kml/>"), Some("kml"));
+        assert_eq!(xml_root_name(b"<!-- unterminated"), None);
+    }
+
+    #[test]
+    fn json_is_only_parsed_for_objects() {
+        assert!(TrackSource::new(br#" {"a": 1}"#).json().is_some());
+        assert!(TrackSource::new(b"[1, 2]").json().is_none());
+        assert!(TrackSource::new(b"{ not json").json().is_none());
+        assert!(TrackSource::new(b"{ not json").into_json().is_err());
+    }
+}
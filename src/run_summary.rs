@@ -0,0 +1,340 @@
+use std::{
+    fmt::Display,
+    fs::File,
+    io::BufWriter,
+    path::{Path, PathBuf},
+};
+
+use serde_json::json;
+
+use crate::{
+    image_geotagger::ImageLocation,
+    models::result::{GTError, GTResult},
+};
+
+/// What happened to an image during a run.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ImageOutcome {
+    Tagged,
+    /// The image was tagged across a gap in the flight track longer than `--max-gap`, with
+    /// `--on-gap flag`.
+    Flagged,
+    /// The image has no EXIF capture time to place it with.
+    Skipped,
+    /// The image was taken outside the flight track, or in a gap in it.
+    OutOfRange,
+    Failed,
+}
+
+impl ImageOutcome {
+    const ALL: [Self; 5] = [
+        Self::Tagged,
+        Self::Flagged,
+        Self::Skipped,
+        Self::OutOfRange,
+        Self::Failed,
+    ];
+
+    /// Whether the image was written with its new position.
+    pub fn is_tagged(&self) -> bool {
+        matches!(self, Self::Tagged | Self::Flagged)
+    }
+}
+
+impl From<&GTResult<ImageLocation>> for ImageOutcome {
+    fn from(result: &GTResult<ImageLocation>) -> Self {
+        match result {
+            Ok(location) if location.gap_exceeded => Self::Flagged,
+            Ok(_) => Self::Tagged,
+            Err(GTError::MissingExif(_)) => Self::Skipped,
+            Err(GTError::OutOfRange(_)) => Self::OutOfRange,
+            Err(_) => Self::Failed,
+        }
+    }
+}
+
+impl Display for ImageOutcome {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let text = match self {
+            Self::Tagged => "tagged",
+            Self::Flagged => "flagged",
+            Self::Skipped => "skipped",
+            Self::OutOfRange => "out-of-range",
+            Self::Failed => "failed",
+        };
+        f.write_str(text)
+    }
+}
+
+/// The file format of a `--report`, chosen by its extension.
+#[derive(Clone, Copy, Debug)]
+pub enum ReportFormat {
+    Json,
+    Csv,
+}
+
+impl ReportFormat {
+    pub fn try_from_path(path: &Path) -> GTResult<Self> {
+        match path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .map(|ext| ext.to_ascii_lowercase())
+            .as_deref()
+        {
+            Some("json") => Ok(Self::Json),
+            Some("csv") => Ok(Self::Csv),
+            _ => Err(GTError::Args(format!(
+                "Report '{}' should be a .json or .csv file.",
+                path.display()
+            ))),
+        }
+    }
+}
+
+/// The outcome of every image in a run, in the order they were processed.
+#[derive(Default)]
+pub struct RunSummary {
+    rows: Vec<(PathBuf, ImageOutcome, Option<String>)>,
+}
+
+impl RunSummary {
+    /// Some images were not geotagged. Clap already uses 2 for command line errors.
+    pub const EXIT_SOME_FAILED: i32 = 3;
+    /// No images were geotagged.
+    pub const EXIT_ALL_FAILED: i32 = 4;
+
+    pub fn add(&mut self, relative_path: PathBuf, result: &GTResult<ImageLocation>) {
+        let reason = match result {
+            Ok(location) if location.gap_exceeded => Some(format!(
+                "Interpolated across a {}s gap in the flight track.",
+                location.fix.gap().num_seconds()
+            )),
+            Ok(_) => None,
+            Err(e) => Some(e.to_string()),
+        };
+        self.rows.push((relative_path, result.into(), reason));
+    }
+
+    pub fn count(&self, outcome: ImageOutcome) -> usize {
+        self.rows.iter().filter(|(_, o, _)| *o == outcome).count()
+    }
+
+    /// The process exit code for the run: zero if every image was geotagged, even if flagged.
+    pub fn exit_code(&self) -> i32 {
+        let tagged = self.rows.iter().filter(|(_, o, _)| o.is_tagged()).count();
+
+        match tagged {
+            tagged if tagged == self.rows.len() => 0,
+            0 => Self::EXIT_ALL_FAILED,
+            _ => Self::EXIT_SOME_FAILED,
+        }
+    }
+
+    pub fn print(&self) {
+        println!("\nSummary of {} images:", self.rows.len());
+        for outcome in ImageOutcome::ALL {
+            println!("  {:<12} {}", outcome.to_string(), self.count(outcome));
+        }
+
+        for (heading, tagged) in [("Images flagged:", true), ("Images not geotagged:", false)] {
+            let rows = self
+                .rows
+                .iter()
+                .filter(|(_, outcome, _)| outcome.is_tagged() == tagged)
+                .filter_map(|(path, _, reason)| Some((path, reason.as_ref()?)))
+                .collect::<Vec<_>>();
+
+            if !rows.is_empty() {
+                println!("\n{heading}");
+                for (path, reason) in rows {
+                    println!("  {}: {reason}", path.display());
+                }
+            }
+        }
+    }
+
+    pub fn write_report(&self, path: &Path, format: ReportFormat) -> GTResult<()> {
+        match format {
+            ReportFormat::Json => self.write_json(path)?,
+            ReportFormat::Csv => self.write_csv(path)?,
+        }
+
+        println!("Wrote run report to '{}'.", path.display());
+
+        Ok(())
+    }
+
+    fn write_json(&self, path: &Path) -> GTResult<()> {
+        let counts = ImageOutcome::ALL
+            .iter()
+            .map(|outcome| (outcome.to_string(), json!(self.count(*outcome))))
+            .collect::<serde_json::Map<_, _>>();
+
+        let images = self
+            .rows
+            .iter()
+            .map(|(path, outcome, reason)| {
+                json!({
+                    "file": path.display().to_string(),
+                    "outcome": outcome.to_string(),
+                    "reason": reason,
+                })
+            })
+            .collect::<Vec<_>>();
+
+        let report = json!({
+            "total": self.rows.len(),
+            "counts": counts,
+            "exit_code": self.exit_code(),
+            "images": images,
+        });
+
+        let writer = BufWriter::new(File::create(path)?);
+        serde_json::to_writer_pretty(writer, &report)?;
+
+        Ok(())
+    }
+
+    fn write_csv(&self, path: &Path) -> GTResult<()> {
+        let mut writer = csv::Writer::from_path(path)?;
+
+        writer.write_record(["file", "outcome", "reason"])?;
+        for (path, outcome, reason) in &self.rows {
+            writer.write_record([
+                path.display().to_string(),
+                outcome.to_string(),
+                reason.clone().unwrap_or_default(),
+            ])?;
+        }
+        writer.flush()?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use chrono::{DateTime, TimeDelta, Utc};
+
+    use super::{ImageOutcome, ReportFormat, RunSummary};
+    use crate::{
+        image_geotagger::ImageLocation,
+        models::{
+            camera_clock::{ImageTimestamp, TimestampSource},
+            flight_geodata::{GeoPosition, TrackFix},
+            result::{GTError, GTResult},
+        },
+    };
+
+    fn location(gap_exceeded: bool) -> GTResult<ImageLocation> {
+        let position = |seconds| GeoPosition {
+            timestamp: DateTime::<Utc>::UNIX_EPOCH + TimeDelta::seconds(seconds),
+            latitude: 0.0,
+            longitude: 0.0,
+            altitude: 0,
+            speed: None,
+            heading: None,
+            vertical_speed: None,
+        };
+
+        Ok(ImageLocation {
+            timestamp: ImageTimestamp {
+                time: DateTime::<Utc>::UNIX_EPOCH,
+                source: TimestampSource::OffsetTimeOriginal,
+            },
+            fix: TrackFix {
+                position: position(300),
+                before: position(0),
+                after: position(900),
+            },
+            gap_exceeded,
+        })
+    }
+
+    fn summary(results: Vec<GTResult<ImageLocation>>) -> RunSummary {
+        let mut summary = RunSummary::default();
+        for (i, result) in results.iter().enumerate() {
+            summary.add(PathBuf::from(format!("{i}.jpg")), result);
+        }
+        summary
+    }
+
+    #[test]
+    fn results_map_to_outcomes() {
+        let outcome = |result: GTResult<ImageLocation>| ImageOutcome::from(&result);
+
+        assert_eq!(outcome(location(false)), ImageOutcome::Tagged);
+        assert_eq!(outcome(location(true)), ImageOutcome::Flagged);
+        assert_eq!(
+            outcome(Err(GTError::MissingExif(String::new()))),
+            ImageOutcome::Skipped
+        );
+        assert_eq!(
+            outcome(Err(GTError::OutOfRange(String::new()))),
+            ImageOutcome::OutOfRange
+        );
+        assert_eq!(
+            outcome(Err(GTError::MissingData(String::new()))),
+            ImageOutcome::Failed
+        );
+    }
+
+    #[test]
+    fn exit_code_reflects_how_many_images_were_tagged() {
+        assert_eq!(summary(Vec::new()).exit_code(), 0);
+        assert_eq!(
+            summary(vec![location(false), location(true)]).exit_code(),
+            0
+        );
+        assert_eq!(
+            summary(vec![location(false), Err(GTError::Parser)]).exit_code(),
+            RunSummary::EXIT_SOME_FAILED
+        );
+        assert_eq!(
+            summary(vec![
+                Err(GTError::MissingExif(String::new())),
+                Err(GTError::Parser)
+            ])
+            .exit_code(),
+            RunSummary::EXIT_ALL_FAILED
+        );
+    }
+
+    #[test]
+    fn reports_list_flagged_images() {
+        let dir = std::env::temp_dir().join(format!("run-report-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let summary = summary(vec![location(false), location(true), Err(GTError::Parser)]);
+
+        let json_path = dir.join("report.json");
+        summary
+            .write_report(&json_path, ReportFormat::Json)
+            .unwrap_or_else(|e| panic!("{e}"));
+        let report: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(&json_path).unwrap()).unwrap();
+
+        assert_eq!(report["counts"]["tagged"], 1);
+        assert_eq!(report["counts"]["flagged"], 1);
+        assert_eq!(report["counts"]["failed"], 1);
+        assert_eq!(report["exit_code"], RunSummary::EXIT_SOME_FAILED);
+        assert_eq!(report["images"][1]["file"], "1.jpg");
+        assert_eq!(report["images"][1]["outcome"], "flagged");
+        assert_eq!(
+            report["images"][1]["reason"],
+            "Interpolated across a 900s gap in the flight track."
+        );
+
+        let csv_path = dir.join("report.csv");
+        summary
+            .write_report(&csv_path, ReportFormat::Csv)
+            .unwrap_or_else(|e| panic!("{e}"));
+        let csv = std::fs::read_to_string(&csv_path).unwrap();
+
+        assert!(csv.contains("1.jpg,flagged,Interpolated across a 900s gap in the flight track."));
+        assert!(csv.contains("0.jpg,tagged,\n"));
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,90 @@
+use std::{
+    collections::BTreeMap,
+    num::NonZeroUsize,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        mpsc,
+    },
+    thread,
+};
+
+/// Runs `work` on each of `items` across up to `jobs` threads.
+///
+/// Results are handed to `done` on the calling thread in the order of `items`, each as soon as it
+/// and every result before it are ready, so the output is the same as a serial run.
+pub fn process_in_order<T, R>(
+    items: &[T],
+    jobs: NonZeroUsize,
+    work: impl Fn(&T) -> R + Sync,
+    mut done: impl FnMut(&T, R),
+) where
+    T: Sync,
+    R: Send,
+{
+    let next_item = AtomicUsize::new(0);
+    let (sender, receiver) = mpsc::channel();
+
+    thread::scope(|scope| {
+        for _ in 0..jobs.get().min(items.len()) {
+            let sender = sender.clone();
+            let (next_item, work) = (&next_item, &work);
+
+            scope.spawn(move || loop {
+                let index = next_item.fetch_add(1, Ordering::Relaxed);
+                let Some(item) = items.get(index) else {
+                    break;
+                };
+
+                if sender.send((index, work(item))).is_err() {
+                    break;
+                }
+            });
+        }
+        drop(sender);
+
+        // Results finishing ahead of an earlier item are held until it's done.
+        let mut pending = BTreeMap::new();
+        let mut next_done = 0;
+
+        for (index, result) in receiver {
+            pending.insert(index, result);
+
+            while let Some(result) = pending.remove(&next_done) {
+                done(&items[next_done], result);
+                next_done += 1;
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{num::NonZeroUsize, thread, time::Duration};
+
+    use super::process_in_order;
+
+    #[test]
+    fn results_are_handed_over_in_item_order() {
+        let items = (0..50).collect::<Vec<u64>>();
+        let mut results = Vec::new();
+
+        process_in_order(
+            &items,
+            NonZeroUsize::new(8).unwrap(),
+            |item| {
+                // Later items finish first.
+                thread::sleep(Duration::from_millis(50 - item));
+                item * 2
+            },
+            |item, result| results.push((*item, result)),
+        );
+
+        assert_eq!(
+            results,
+            items
+                .iter()
+                .map(|item| (*item, item * 2))
+                .collect::<Vec<_>>()
+        );
+    }
+}